@@ -0,0 +1,222 @@
+use crate::conjugation::{self, Auxiliary, Gender, Number, Person, Tense};
+use crate::pronouns;
+use crate::template::Template;
+use crate::verbs::Verb;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Pronominal verbs whose reflexive pronoun is an indirect object ("se parler" is
+/// "parler à soi"), so that their participle does not agree with the subject
+const INDIRECT_REFLEXIVE_VERBS: &[&str] = &[
+    "convenir",
+    "déplaire",
+    "mentir",
+    "nuire",
+    "parler",
+    "plaire",
+    "ressembler",
+    "rire",
+    "sourire",
+    "succéder",
+    "suffire",
+    "survivre",
+    "téléphoner",
+];
+
+/// Situation of a past participle, read from the query string
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AgreementQuery {
+    /// Auxiliary of the compound form; the verb's own when omitted
+    pub auxiliary: Option<Auxiliary>,
+    pub pronominal: bool,
+    /// Gender and number of the subject
+    pub gender: Gender,
+    pub number: Number,
+    /// Gender and number of a direct object placed before the verb ("la pomme
+    /// qu'il a mangée"); no such object when both are omitted
+    pub object_gender: Option<Gender>,
+    pub object_number: Option<Number>,
+}
+
+/// Rule deciding the form of a past participle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AgreementRule {
+    /// With "être", the participle agrees with the subject
+    EtreSubject,
+    /// With "avoir" and no preceding direct object, the participle is invariable
+    AvoirInvariable,
+    /// With "avoir", the participle agrees with a preceding direct object
+    AvoirPrecedingObject,
+    /// The reflexive pronoun is the direct object: agreement with the subject
+    PronominalSubject,
+    /// The reflexive pronoun is an indirect object: the participle is invariable
+    PronominalIndirect,
+    /// A direct object other than the reflexive pronoun precedes the verb
+    PronominalPrecedingObject,
+}
+
+impl AgreementRule {
+    pub fn explanation(self) -> &'static str {
+        match self {
+            AgreementRule::EtreSubject => {
+                "Conjugated with être, the past participle agrees in gender and number with the subject."
+            }
+            AgreementRule::AvoirInvariable => {
+                "Conjugated with avoir, the past participle does not agree with the subject, and no direct object precedes the verb: it stays invariable."
+            }
+            AgreementRule::AvoirPrecedingObject => {
+                "Conjugated with avoir, the past participle agrees in gender and number with the direct object placed before the verb."
+            }
+            AgreementRule::PronominalSubject => {
+                "In a pronominal verb whose reflexive pronoun is the direct object (or an essentially pronominal verb), the past participle agrees with the subject."
+            }
+            AgreementRule::PronominalIndirect => {
+                "In a pronominal verb whose reflexive pronoun is an indirect object (se parler = parler à soi), the past participle stays invariable."
+            }
+            AgreementRule::PronominalPrecedingObject => {
+                "In a pronominal verb with a direct object placed before the verb, the past participle agrees with that object rather than with the subject."
+            }
+        }
+    }
+}
+
+/// Gender and number a participle agrees in
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Inflection {
+    pub gender: Gender,
+    pub number: Number,
+}
+
+/// Agreed past participle and a passé composé using it
+#[derive(Debug, Clone, Serialize)]
+pub struct Agreement {
+    pub verb: String,
+    pub auxiliary: Auxiliary,
+    pub pronominal: bool,
+    /// Every variant of the agreed participle
+    pub participles: Vec<String>,
+    /// Gender and number the participle takes; `None` when it is invariable
+    pub agrees_with: Option<Inflection>,
+    /// Third-person passé composé showing the participle, with an object pronoun
+    /// standing for a preceding direct object ("elle l'a mangée")
+    pub compound: Vec<String>,
+    pub rule: AgreementRule,
+    pub explanation: &'static str,
+}
+
+fn rule(verb: &str, auxiliary: Auxiliary, pronominal: bool, object: bool) -> AgreementRule {
+    match (pronominal, auxiliary, object) {
+        (true, _, true) => AgreementRule::PronominalPrecedingObject,
+        (true, _, false) if INDIRECT_REFLEXIVE_VERBS.contains(&verb) => {
+            AgreementRule::PronominalIndirect
+        }
+        (true, _, false) => AgreementRule::PronominalSubject,
+        (false, Auxiliary::Etre, _) => AgreementRule::EtreSubject,
+        (false, Auxiliary::Avoir, true) => AgreementRule::AvoirPrecedingObject,
+        (false, Auxiliary::Avoir, false) => AgreementRule::AvoirInvariable,
+    }
+}
+
+/// Object pronoun standing for a direct object of the given gender and number
+fn object_pronoun(gender: Gender, number: Number) -> &'static str {
+    match (gender, number) {
+        (Gender::Masculine, Number::Singular) => "le",
+        (Gender::Feminine, Number::Singular) => "la",
+        (_, Number::Plural) => "les",
+    }
+}
+
+fn subject_pronoun(gender: Gender, number: Number) -> &'static str {
+    match (gender, number) {
+        (Gender::Masculine, Number::Singular) => "il",
+        (Gender::Feminine, Number::Singular) => "elle",
+        (Gender::Masculine, Number::Plural) => "ils",
+        (Gender::Feminine, Number::Plural) => "elles",
+    }
+}
+
+/// Agree the past participle of a verb according to its auxiliary, subject and
+/// preceding direct object
+pub fn agree(
+    verb: &Verb,
+    templates: &HashMap<String, Template>,
+    query: &AgreementQuery,
+) -> Option<Agreement> {
+    let forms = conjugation::simple_forms(verb, templates.get(&verb.template)?)?;
+    let auxiliary = query
+        .auxiliary
+        .unwrap_or_else(|| conjugation::auxiliary(&verb.verb, query.pronominal));
+    let auxiliary_verb = auxiliary.verb();
+    let auxiliary_forms =
+        conjugation::simple_forms(&auxiliary_verb, templates.get(&auxiliary_verb.template)?)?;
+
+    let object =
+        (query.object_gender.is_some() || query.object_number.is_some()).then(|| Inflection {
+            gender: query.object_gender.unwrap_or_default(),
+            number: query.object_number.unwrap_or_default(),
+        });
+    let rule = rule(&verb.verb, auxiliary, query.pronominal, object.is_some());
+    let agrees_with = match rule {
+        AgreementRule::EtreSubject | AgreementRule::PronominalSubject => Some(Inflection {
+            gender: query.gender,
+            number: query.number,
+        }),
+        AgreementRule::AvoirPrecedingObject | AgreementRule::PronominalPrecedingObject => object,
+        AgreementRule::AvoirInvariable | AgreementRule::PronominalIndirect => None,
+    };
+    let index = agrees_with
+        .map(|inflection| conjugation::past_participle_index(inflection.gender, inflection.number))
+        .unwrap_or(0);
+    let participles = forms
+        .get(&Tense::PastParticiple)
+        .and_then(|cells| cells.get(index))
+        .cloned()
+        .unwrap_or_default();
+
+    let person = match query.number {
+        Number::Singular => Person::ThirdSingular,
+        Number::Plural => Person::ThirdPlural,
+    };
+    let person_index = Person::ALL.iter().position(|&p| p == person).unwrap_or(0);
+    let subject = subject_pronoun(query.gender, query.number);
+    let clitics: Vec<&str> = [
+        query
+            .pronominal
+            .then(|| pronouns::reflexive_pronoun(person)),
+        object.map(|inflection| object_pronoun(inflection.gender, inflection.number)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let compound = auxiliary_forms
+        .get(&Tense::IndicativePresent)
+        .and_then(|cells| cells.get(person_index))
+        .into_iter()
+        .flatten()
+        .flat_map(|auxiliary| {
+            let phrase = clitics
+                .iter()
+                .rev()
+                .fold(auxiliary.clone(), |text, clitic| {
+                    pronouns::elide(clitic, &text, false)
+                });
+            let phrase = pronouns::elide(subject, &phrase, false);
+            participles
+                .iter()
+                .map(move |participle| format!("{phrase} {participle}"))
+        })
+        .collect();
+
+    Some(Agreement {
+        verb: verb.verb.clone(),
+        auxiliary,
+        pronominal: query.pronominal,
+        participles,
+        agrees_with,
+        compound,
+        rule,
+        explanation: rule.explanation(),
+    })
+}
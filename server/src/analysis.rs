@@ -0,0 +1,260 @@
+use crate::conjugation::{self, Tense};
+use crate::template::Template;
+use crate::verbs::Verb;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Templates differing in at most this many cells are reported as near duplicates
+pub const DEFAULT_MAX_DIFFERENCES: usize = 3;
+
+/// A template reduced to its endings, independent of where its name puts the colon.
+/// The longest prefix shared by all endings is moved into the stem, so that
+/// "pla:cer" (cer, ce, çons...) and "pl:acer" (acer, ace, açons...) compare equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct NormalizedTemplate {
+    cells: Vec<(Tense, usize, Vec<String>)>,
+}
+
+/// One cell that differs between two templates
+#[derive(Debug, Clone, Serialize)]
+pub struct CellDiff {
+    pub tense: Tense,
+    pub person: usize,
+    pub left: Vec<String>,
+    pub right: Vec<String>,
+}
+
+/// Two templates whose normalized endings differ in only a few cells
+#[derive(Debug, Clone, Serialize)]
+pub struct NearDuplicate {
+    pub left: String,
+    pub right: String,
+    pub differences: Vec<CellDiff>,
+}
+
+/// Findings on the consistency of the conjugation data
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateReport {
+    /// Groups of templates with identical normalized endings
+    pub duplicates: Vec<Vec<String>>,
+    pub near_duplicates: Vec<NearDuplicate>,
+    /// Templates that no verb refers to
+    pub unused: Vec<String>,
+}
+
+/// Characters of the infinitive termination that normalization always keeps,
+/// so that templates of defective verbs reduced to their infinitive ("est:er",
+/// "qu:érir") are not all collapsed into an empty termination
+const MIN_TERMINATION_LEN: usize = 2;
+
+/// Length in bytes of the longest prefix shared by all endings
+fn shared_prefix_len(template: &Template) -> usize {
+    let endings: Vec<String> = Tense::SIMPLE
+        .iter()
+        .flat_map(|&tense| conjugation::endings(template, tense))
+        .flatten()
+        .collect();
+    let Some(first) = endings.first() else {
+        return 0;
+    };
+    let infinitive_len = template
+        .infinitive
+        .infinitive_present
+        .first()
+        .map(|ending| ending.chars().count())
+        .unwrap_or(0);
+    first
+        .char_indices()
+        .take(infinitive_len.saturating_sub(MIN_TERMINATION_LEN))
+        .map(|(idx, c)| idx + c.len_utf8())
+        .take_while(|&len| {
+            endings
+                .iter()
+                .all(|ending| ending.get(..len) == first.get(..len))
+        })
+        .last()
+        .unwrap_or(0)
+}
+
+fn normalize(template: &Template) -> NormalizedTemplate {
+    let shared = shared_prefix_len(template);
+    let cells = Tense::SIMPLE
+        .iter()
+        .flat_map(|&tense| {
+            conjugation::endings(template, tense)
+                .into_iter()
+                .enumerate()
+                .map(move |(person, cell)| {
+                    let cell = cell
+                        .iter()
+                        .map(|ending| ending[shared..].to_string())
+                        .collect();
+                    (tense, person, cell)
+                })
+        })
+        .collect();
+    NormalizedTemplate { cells }
+}
+
+fn diff(left: &NormalizedTemplate, right: &NormalizedTemplate) -> Vec<CellDiff> {
+    left.cells
+        .iter()
+        .zip(&right.cells)
+        .filter(|(l, r)| l != r)
+        .map(|((tense, person, l), (_, _, r))| CellDiff {
+            tense: *tense,
+            person: *person,
+            left: l.clone(),
+            right: r.clone(),
+        })
+        .collect()
+}
+
+/// Report duplicate, near-duplicate and unused templates
+pub fn analyze_templates(
+    templates: &HashMap<String, Template>,
+    verbs: &[Verb],
+    max_differences: usize,
+) -> TemplateReport {
+    let mut names: Vec<&String> = templates.keys().collect();
+    names.sort();
+    let normalized: Vec<NormalizedTemplate> = names
+        .iter()
+        .map(|name| normalize(&templates[*name]))
+        .collect();
+
+    let mut groups: HashMap<&NormalizedTemplate, Vec<String>> = HashMap::new();
+    for (name, endings) in names.iter().zip(&normalized) {
+        groups.entry(endings).or_default().push(name.to_string());
+    }
+    let mut duplicates: Vec<Vec<String>> = groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+    duplicates.sort();
+
+    let mut near_duplicates = Vec::new();
+    for i in 0..names.len() {
+        for j in (i + 1)..names.len() {
+            // Differently shaped templates cannot be compared cell by cell
+            if normalized[i].cells.len() != normalized[j].cells.len() {
+                continue;
+            }
+            let differences = diff(&normalized[i], &normalized[j]);
+            if !differences.is_empty() && differences.len() <= max_differences {
+                near_duplicates.push(NearDuplicate {
+                    left: names[i].clone(),
+                    right: names[j].clone(),
+                    differences,
+                });
+            }
+        }
+    }
+
+    let used: HashSet<&str> = verbs.iter().map(|verb| verb.template.as_str()).collect();
+    let unused = names
+        .iter()
+        .filter(|name| !used.contains(name.as_str()))
+        .map(|name| name.to_string())
+        .collect();
+
+    TemplateReport {
+        duplicates,
+        near_duplicates,
+        unused,
+    }
+}
+
+/// Human-readable rendering of a report
+pub fn format_report(report: &TemplateReport) -> String {
+    let mut out = String::new();
+
+    out.push_str(&format!(
+        "Duplicate templates ({}):\n",
+        report.duplicates.len()
+    ));
+    for group in &report.duplicates {
+        out.push_str(&format!("  {}\n", group.join(" = ")));
+    }
+
+    out.push_str(&format!(
+        "\nNear-duplicate templates ({}):\n",
+        report.near_duplicates.len()
+    ));
+    for near in &report.near_duplicates {
+        out.push_str(&format!(
+            "  {} ~ {} ({} cell(s))\n",
+            near.left,
+            near.right,
+            near.differences.len()
+        ));
+        for difference in &near.differences {
+            out.push_str(&format!(
+                "    {}[{}]: {} | {}\n",
+                difference.tense.name(),
+                difference.person,
+                difference.left.join(","),
+                difference.right.join(",")
+            ));
+        }
+    }
+
+    out.push_str(&format!("\nUnused templates ({}):\n", report.unused.len()));
+    for name in &report.unused {
+        out.push_str(&format!("  {}\n", name));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::load_all_templates;
+    use crate::verbs::load_all_verbs;
+
+    const VERBS_XML: &str = include_str!("../../data/verbs-fr.xml");
+    const CONJUGATION_XML: &str = include_str!("../../data/conjugation-fr.xml");
+
+    #[test]
+    fn known_duplicates_are_reported() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let report = analyze_templates(&templates, &verbs, 0);
+
+        let pair = |left: &str, right: &str| vec![left.to_string(), right.to_string()];
+        // "craindre" and "joindre" differ only in the stem kept by the template name
+        assert!(report.duplicates.contains(&pair("crai:ndre", "oi:ndre")));
+        assert!(report.duplicates.contains(&pair("aim:er", "grasse:yer")));
+        assert!(report.near_duplicates.is_empty());
+        assert!(report.unused.is_empty());
+    }
+
+    #[test]
+    fn near_duplicates_list_their_differences() {
+        let mut templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let mut changed = templates["fin:ir"].clone();
+        changed.name = "zz:ir".to_string();
+        changed.indicative.present[0] = vec!["ix".to_string()];
+        templates.insert(changed.name.clone(), changed);
+        let verbs = [Verb {
+            verb: "finir".to_string(),
+            template: "fin:ir".to_string(),
+            aspirate_h: false,
+        }];
+
+        let report = analyze_templates(&templates, &verbs, 1);
+        let near = report
+            .near_duplicates
+            .iter()
+            .find(|near| near.left == "fin:ir" && near.right == "zz:ir")
+            .unwrap();
+        assert_eq!(near.differences.len(), 1);
+        assert_eq!(near.differences[0].tense, Tense::IndicativePresent);
+        assert_eq!(near.differences[0].person, 0);
+        assert_eq!(near.differences[0].left, ["is"]);
+        assert_eq!(near.differences[0].right, ["ix"]);
+        assert!(report.unused.contains(&"zz:ir".to_string()));
+        assert!(!report.unused.contains(&"fin:ir".to_string()));
+    }
+}
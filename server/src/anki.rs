@@ -0,0 +1,482 @@
+use crate::conjugation::{self, ConjugationOptions, Mood, Tense};
+use crate::pronouns::PronounSet;
+use crate::properties;
+use crate::render::escape_html;
+use crate::template::Template;
+use crate::verbs::Verb;
+use crate::worksheet;
+use rusqlite::{Connection, params};
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Front of the cards when none is given: the sentence or tense to conjugate
+pub const DEFAULT_FRONT: &str = "{{Prompt}}";
+/// Back of the cards when none is given: the question and its answer
+pub const DEFAULT_BACK: &str = "{{FrontSide}}<hr id=answer>{{Answer}}";
+
+/// Fields of the note type, the first one being unique to each note
+const FIELDS: [&str; 5] = ["Prompt", "Answer", "Verb", "Tense", "Person"];
+
+const CSS: &str = ".card { font-family: arial; font-size: 24px; text-align: center; \
+                   color: black; background-color: white; }";
+
+/// Tables of an Anki collection (schema version 11), as read by the importer
+const SCHEMA: &str = "
+CREATE TABLE col (
+    id INTEGER PRIMARY KEY, crt INTEGER NOT NULL, mod INTEGER NOT NULL,
+    scm INTEGER NOT NULL, ver INTEGER NOT NULL, dty INTEGER NOT NULL,
+    usn INTEGER NOT NULL, ls INTEGER NOT NULL, conf TEXT NOT NULL,
+    models TEXT NOT NULL, decks TEXT NOT NULL, dconf TEXT NOT NULL, tags TEXT NOT NULL
+);
+CREATE TABLE notes (
+    id INTEGER PRIMARY KEY, guid TEXT NOT NULL, mid INTEGER NOT NULL,
+    mod INTEGER NOT NULL, usn INTEGER NOT NULL, tags TEXT NOT NULL,
+    flds TEXT NOT NULL, sfld INTEGER NOT NULL, csum INTEGER NOT NULL,
+    flags INTEGER NOT NULL, data TEXT NOT NULL
+);
+CREATE TABLE cards (
+    id INTEGER PRIMARY KEY, nid INTEGER NOT NULL, did INTEGER NOT NULL,
+    ord INTEGER NOT NULL, mod INTEGER NOT NULL, usn INTEGER NOT NULL,
+    type INTEGER NOT NULL, queue INTEGER NOT NULL, due INTEGER NOT NULL,
+    ivl INTEGER NOT NULL, factor INTEGER NOT NULL, reps INTEGER NOT NULL,
+    lapses INTEGER NOT NULL, left INTEGER NOT NULL, odue INTEGER NOT NULL,
+    odid INTEGER NOT NULL, flags INTEGER NOT NULL, data TEXT NOT NULL
+);
+CREATE TABLE revlog (
+    id INTEGER PRIMARY KEY, cid INTEGER NOT NULL, usn INTEGER NOT NULL,
+    ease INTEGER NOT NULL, ivl INTEGER NOT NULL, lastIvl INTEGER NOT NULL,
+    factor INTEGER NOT NULL, time INTEGER NOT NULL, type INTEGER NOT NULL
+);
+CREATE TABLE graves (usn INTEGER NOT NULL, oid INTEGER NOT NULL, type INTEGER NOT NULL);
+CREATE INDEX ix_notes_usn ON notes (usn);
+CREATE INDEX ix_cards_usn ON cards (usn);
+CREATE INDEX ix_revlog_usn ON revlog (usn);
+CREATE INDEX ix_cards_nid ON cards (nid);
+CREATE INDEX ix_cards_sched ON cards (did, queue, due);
+CREATE INDEX ix_revlog_cid ON revlog (cid);
+CREATE INDEX ix_notes_csum ON notes (csum);
+";
+
+/// Content and layout of an exported deck
+#[derive(Debug, Clone)]
+pub struct AnkiOptions {
+    pub deck: String,
+    /// One note per verb × tense × person instead of one per verb × tense
+    pub per_person: bool,
+    pub tenses: Vec<Tense>,
+    /// Card templates, in Anki syntax over the fields Prompt, Answer, Verb,
+    /// Tense and Person
+    pub front: String,
+    pub back: String,
+}
+
+/// One note of the deck
+struct Note {
+    /// Identity of the note across exports, from which its GUID and ids derive
+    key: String,
+    fields: [String; 5],
+    tags: Vec<String>,
+}
+
+/// SHA-1 digest of a string
+fn digest(text: &str) -> [u8; 20] {
+    sha1_smol::Sha1::from(text).digest().bytes()
+}
+
+/// Positive 52-bit id derived from a key, so that ids are stable across exports
+/// and fit JavaScript numbers
+fn stable_id(key: &str) -> i64 {
+    let bytes = digest(key);
+    let id = i64::from_be_bytes(bytes[..8].try_into().unwrap_or_default());
+    (id & 0x000f_ffff_ffff_ffff).max(2)
+}
+
+/// Note GUID derived from its key, letting Anki update notes on re-import
+fn guid(key: &str) -> String {
+    digest(key)[..10]
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Field text with its HTML tags removed, as Anki compares fields
+fn strip_html(text: &str) -> String {
+    let mut stripped = String::new();
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => stripped.push(c),
+            _ => {}
+        }
+    }
+    stripped
+}
+
+/// Checksum of the first field, used by Anki to detect duplicates
+fn checksum(field: &str) -> i64 {
+    let bytes = digest(&strip_html(field));
+    i64::from(u32::from_be_bytes(
+        bytes[..4].try_into().unwrap_or_default(),
+    ))
+}
+
+/// Anki tag from a name, which cannot contain spaces
+fn tag(name: &str) -> String {
+    name.replace(' ', "_")
+}
+
+/// Notes of one verb: a table per tense, or a sentence per person
+fn notes(verb: &Verb, templates: &HashMap<String, Template>, options: &AnkiOptions) -> Vec<Note> {
+    let Some(conjugation) = conjugation::conjugate(verb, templates, &ConjugationOptions::default())
+    else {
+        return Vec::new();
+    };
+    let mut tags = vec!["verbiste".to_string(), tag(&verb.template)];
+    if let Some(template) = templates.get(&verb.template) {
+        tags.push(format!("group-{}", properties::group(verb, template)));
+    }
+
+    let mut notes = Vec::new();
+    for table in conjugation
+        .tenses
+        .iter()
+        .filter(|table| options.tenses.contains(&table.tense))
+    {
+        let tense = table.tense;
+        let tags: Vec<String> = tags.iter().cloned().chain([tag(tense.name())]).collect();
+        let cells = table
+            .cells
+            .iter()
+            .filter_map(|cell| Some((cell.person?, cell.forms.as_ref()?, cell.display.as_ref()?)));
+        if options.per_person {
+            for (person, forms, display) in cells {
+                // Impersonal verbs take the neutral "il" only
+                let subjects = if properties::is_impersonal(&verb.verb) {
+                    "il".to_string()
+                } else {
+                    PronounSet::Classic.subjects(person).join("/")
+                };
+                let prompt = worksheet::prompt(
+                    (tense.mood() != Mood::Imperative).then_some(subjects.as_str()),
+                    person,
+                    tense.mood(),
+                    &forms[0],
+                    verb.aspirate_h,
+                );
+                let person_name = person.name();
+                notes.push(Note {
+                    key: format!("verbiste:{}:{}:{}", verb.verb, tense.name(), person_name),
+                    fields: [
+                        format!(
+                            "{} ({}) — {}",
+                            escape_html(&prompt),
+                            escape_html(&verb.verb),
+                            tense.label()
+                        ),
+                        escape_html(&display.join(" / ")),
+                        escape_html(&verb.verb),
+                        tense.label(),
+                        person_name.to_string(),
+                    ],
+                    tags: tags.clone(),
+                });
+            }
+        } else {
+            let lines: Vec<String> = cells
+                .map(|(_, _, display)| escape_html(&display.join(" / ")))
+                .collect();
+            if lines.is_empty() {
+                continue;
+            }
+            notes.push(Note {
+                key: format!("verbiste:{}:{}", verb.verb, tense.name()),
+                fields: [
+                    format!("{} — {}", escape_html(&verb.verb), tense.label()),
+                    lines.join("<br>"),
+                    escape_html(&verb.verb),
+                    tense.label(),
+                    String::new(),
+                ],
+                tags,
+            });
+        }
+    }
+    notes
+}
+
+/// Collection settings, note type and deck of the exported collection
+fn collection_json(options: &AnkiOptions, model_id: i64, deck_id: i64, now: i64) -> [String; 4] {
+    let conf = json!({
+        "activeDecks": [1],
+        "curDeck": 1,
+        "newSpread": 0,
+        "collapseTime": 1200,
+        "timeLim": 0,
+        "estTimes": true,
+        "dueCounts": true,
+        "curModel": null,
+        "nextPos": 1,
+        "sortType": "noteFld",
+        "sortBackwards": false,
+        "addToCur": true,
+    });
+    let fields: Vec<_> = FIELDS
+        .iter()
+        .enumerate()
+        .map(|(ord, name)| {
+            json!({
+                "name": name,
+                "ord": ord,
+                "sticky": false,
+                "rtl": false,
+                "font": "Arial",
+                "size": 20,
+                "media": [],
+            })
+        })
+        .collect();
+    let models = json!({
+        model_id.to_string(): {
+            "id": model_id,
+            "name": "Verbiste conjugation",
+            "type": 0,
+            "mod": now,
+            "usn": -1,
+            "sortf": 0,
+            "did": deck_id,
+            "tmpls": [{
+                "name": "Conjugation",
+                "ord": 0,
+                "qfmt": options.front,
+                "afmt": options.back,
+                "did": null,
+                "bqfmt": "",
+                "bafmt": "",
+            }],
+            "flds": fields,
+            "css": CSS,
+            "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+            "latexPost": "\\end{document}",
+            "tags": [],
+            "vers": [],
+            "req": [[0, "any", [0]]],
+        }
+    });
+    let deck = |id: i64, name: &str| {
+        json!({
+            "id": id,
+            "name": name,
+            "desc": "",
+            "mod": now,
+            "usn": -1,
+            "lrnToday": [0, 0],
+            "revToday": [0, 0],
+            "newToday": [0, 0],
+            "timeToday": [0, 0],
+            "collapsed": false,
+            "browserCollapsed": false,
+            "dyn": 0,
+            "conf": 1,
+            "extendNew": 10,
+            "extendRev": 50,
+        })
+    };
+    let decks = json!({
+        "1": deck(1, "Default"),
+        deck_id.to_string(): deck(deck_id, &options.deck),
+    });
+    let dconf = json!({
+        "1": {
+            "id": 1,
+            "name": "Default",
+            "mod": 0,
+            "usn": 0,
+            "maxTaken": 60,
+            "autoplay": true,
+            "timer": 0,
+            "replayq": true,
+            "dyn": false,
+            "new": {
+                "bury": true,
+                "delays": [1, 10],
+                "initialFactor": 2500,
+                "ints": [1, 4, 7],
+                "order": 1,
+                "perDay": 20,
+                "separate": true,
+            },
+            "lapse": {
+                "delays": [10],
+                "leechAction": 0,
+                "leechFails": 8,
+                "minInt": 1,
+                "mult": 0,
+            },
+            "rev": {
+                "bury": true,
+                "ease4": 1.3,
+                "fuzz": 0.05,
+                "ivlFct": 1,
+                "maxIvl": 36500,
+                "minSpace": 1,
+                "perDay": 100,
+            },
+        }
+    });
+    [
+        conf.to_string(),
+        models.to_string(),
+        decks.to_string(),
+        dconf.to_string(),
+    ]
+}
+
+/// Write the collection of an Anki package to a SQLite file
+fn write_collection(
+    path: &Path,
+    notes: &[Note],
+    options: &AnkiOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as i64;
+    let model_id = stable_id("verbiste:model");
+    let deck_id = stable_id(&format!("verbiste:deck:{}", options.deck));
+    let [conf, models, decks, dconf] = collection_json(options, model_id, deck_id, now);
+
+    let mut connection = Connection::open(path)?;
+    connection.execute_batch(SCHEMA)?;
+    let transaction = connection.transaction()?;
+    transaction.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+        params![now, now * 1000, conf, models, decks, dconf],
+    )?;
+    for (position, note) in notes.iter().enumerate() {
+        let note_id = stable_id(&note.key);
+        transaction.execute(
+            "INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')",
+            params![
+                note_id,
+                guid(&note.key),
+                model_id,
+                now,
+                format!(" {} ", note.tags.join(" ")),
+                note.fields.join("\x1f"),
+                strip_html(&note.fields[0]),
+                checksum(&note.fields[0]),
+            ],
+        )?;
+        transaction.execute(
+            "INSERT INTO cards VALUES (?1, ?2, ?3, 0, ?4, -1, 0, 0, ?5, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+            params![
+                stable_id(&format!("{}:card", note.key)),
+                note_id,
+                deck_id,
+                now,
+                position as i64 + 1,
+            ],
+        )?;
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+/// Export the conjugations of verbs as an Anki package (.apkg): a zip archive
+/// of the SQLite collection and an empty media manifest. Returns the number of notes.
+pub fn export(
+    verbs: &[&Verb],
+    templates: &HashMap<String, Template>,
+    options: &AnkiOptions,
+    output: &Path,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let notes: Vec<Note> = verbs
+        .iter()
+        .flat_map(|verb| notes(verb, templates, options))
+        .collect();
+
+    let collection = std::env::temp_dir().join(format!(
+        "verbiste-{}-{}.anki2",
+        std::process::id(),
+        SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos()
+    ));
+    let written = write_collection(&collection, &notes, options)
+        .and_then(|()| Ok(std::fs::read(&collection)?));
+    let _ = std::fs::remove_file(&collection);
+    let collection = written?;
+
+    let mut archive = zip::ZipWriter::new(std::fs::File::create(output)?);
+    let file_options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    archive.start_file("collection.anki2", file_options)?;
+    archive.write_all(&collection)?;
+    archive.start_file("media", file_options)?;
+    archive.write_all(b"{}")?;
+    archive.finish()?;
+    Ok(notes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::load_all_templates;
+    use crate::verbs::load_all_verbs;
+
+    const VERBS_XML: &str = include_str!("../../data/verbs-fr.xml");
+    const CONJUGATION_XML: &str = include_str!("../../data/conjugation-fr.xml");
+
+    #[test]
+    fn ids_derive_from_the_key() {
+        let key = "verbiste:aimer:indicative-present";
+        assert_eq!(guid(key), "f37a7a259d1be3e2860f");
+        assert_eq!(stable_id(key), 2949051735335906);
+        assert!(stable_id(key) < 1 << 53);
+    }
+
+    #[test]
+    fn exports_keep_note_ids() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let options = AnkiOptions {
+            deck: "Verbes".to_string(),
+            per_person: true,
+            tenses: vec![Tense::IndicativePresent, Tense::SubjunctivePresent],
+            front: DEFAULT_FRONT.to_string(),
+            back: DEFAULT_BACK.to_string(),
+        };
+        let notes: Vec<Note> = ["aimer", "finir"]
+            .iter()
+            .flat_map(|name| {
+                let verb = verbs.iter().find(|v| v.verb == *name).unwrap();
+                notes(verb, &templates, &options)
+            })
+            .collect();
+        assert_eq!(notes.len(), 24);
+
+        let ids = |run: usize| {
+            let path = std::env::temp_dir()
+                .join(format!("verbiste-test-{}-{run}.anki2", std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            write_collection(&path, &notes, &options).unwrap();
+            let connection = Connection::open(&path).unwrap();
+            let mut statement = connection
+                .prepare("SELECT notes.id, guid, cards.id FROM notes JOIN cards ON nid = notes.id")
+                .unwrap();
+            let mut ids: Vec<(i64, String, i64)> = statement
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            ids.sort();
+            std::fs::remove_file(&path).unwrap();
+            ids
+        };
+        let first = ids(0);
+        assert_eq!(first.len(), notes.len());
+        assert_eq!(first, ids(1));
+        let key = "verbiste:aimer:indicative-present:first-singular";
+        assert!(first.contains(&(stable_id(key), guid(key), stable_id(&format!("{key}:card")))));
+    }
+}
@@ -0,0 +1,344 @@
+use crate::analysis;
+use crate::anki::{self, AnkiOptions};
+use crate::dataset;
+use crate::dictionary::{self, DictionaryFormat};
+use crate::drill;
+use crate::export::{self, TableFormat};
+use crate::hunspell;
+use crate::induction;
+use crate::morphology::{self, MorphologyFormat};
+use crate::template::Template;
+use crate::verbs::Verb;
+use crate::worksheet::{self, WorksheetFormat, WorksheetQuery};
+use clap::Subcommand;
+use std::collections::HashMap;
+use std::io::{BufWriter, Read, Write};
+use std::path::PathBuf;
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Find or propose a conjugation template for a full paradigm given as JSON
+    /// (same layout as /api/t, with complete forms instead of endings)
+    Induce {
+        /// Path to the paradigm JSON file, or "-" for standard input
+        paradigm: PathBuf,
+
+        /// Add the verb, and the proposed template if any, to the verbs-fr.xml
+        /// and conjugation-fr.xml of this directory
+        #[arg(long)]
+        data: Option<PathBuf>,
+    },
+    /// Report duplicate, near-duplicate and unused conjugation templates
+    AnalyzeTemplates {
+        /// Report templates differing in at most this many cells as near duplicates
+        #[arg(long, default_value_t = analysis::DEFAULT_MAX_DIFFERENCES)]
+        max_differences: usize,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Generate a fill-in-the-blank conjugation worksheet with its answer key
+    Worksheet {
+        /// Comma-separated infinitives to practice
+        #[arg(long)]
+        verbs: Option<String>,
+
+        /// Only practice verbs of this template ("aim:er")
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Only practice verbs of this conjugation group (1, 2 or 3)
+        #[arg(long)]
+        group: Option<u8>,
+
+        /// Comma-separated tense names
+        #[arg(long, default_value = "indicative-present")]
+        tenses: String,
+
+        /// Number of exercises
+        #[arg(long, default_value_t = worksheet::DEFAULT_COUNT)]
+        count: usize,
+
+        /// Seed reproducing a previous worksheet
+        #[arg(long)]
+        seed: Option<u64>,
+
+        #[arg(long)]
+        title: Option<String>,
+
+        #[arg(long, value_enum, default_value_t = WorksheetFormat::Html)]
+        format: WorksheetFormat,
+
+        /// Output file, or "-" for standard output
+        #[arg(long, short, default_value = "-")]
+        output: PathBuf,
+    },
+    /// Export conjugations as an Anki deck package (.apkg)
+    Anki {
+        /// Comma-separated infinitives; every verb when omitted
+        #[arg(long)]
+        verbs: Option<String>,
+
+        /// Only export verbs of this template ("aim:er")
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Only export verbs of this conjugation group (1, 2 or 3)
+        #[arg(long)]
+        group: Option<u8>,
+
+        /// Comma-separated tense names; every tense with persons when omitted
+        #[arg(long)]
+        tenses: Option<String>,
+
+        /// One note per person instead of one per tense
+        #[arg(long)]
+        per_person: bool,
+
+        /// Name of the deck
+        #[arg(long, default_value = "Verbiste")]
+        deck: String,
+
+        /// Front card template, over the fields Prompt, Answer, Verb, Tense and Person
+        #[arg(long, default_value = anki::DEFAULT_FRONT)]
+        front: String,
+
+        /// Back card template
+        #[arg(long, default_value = anki::DEFAULT_BACK)]
+        back: String,
+
+        /// Path of the .apkg file to write
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Export every form of every verb as a flat CSV or TSV table
+    Export {
+        #[arg(long, value_enum, default_value_t = TableFormat::Csv)]
+        format: TableFormat,
+
+        /// Output file, or "-" for standard output
+        #[arg(long, short, default_value = "-")]
+        output: PathBuf,
+    },
+    /// Build a SQLite database of verbs, templates, template cells and forms
+    Sqlite {
+        /// Path of the database to write; an existing file is replaced
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Generate a Hunspell dictionary (.aff and .dic) of every verb form
+    Hunspell {
+        /// Path of the files to write, without extension ("fr-verbs" writes
+        /// fr-verbs.aff and fr-verbs.dic)
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Build an e-reader dictionary of conjugation tables in which every
+    /// inflected form looks up its infinitive
+    Dictionary {
+        #[arg(long, value_enum, default_value_t = DictionaryFormat::Stardict)]
+        format: DictionaryFormat,
+
+        /// Path of the files to write: without extension for StarDict
+        /// ("verbiste" writes verbiste.ifo, .idx, .dict and .syn), the .epub
+        /// file for Kindle
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Export every simple form with its morphological features, as UniMorph
+    /// triples or CoNLL-U
+    Morphology {
+        #[arg(long, value_enum, default_value_t = MorphologyFormat::Unimorph)]
+        format: MorphologyFormat,
+
+        /// Output file, or "-" for standard output
+        #[arg(long, short, default_value = "-")]
+        output: PathBuf,
+    },
+}
+
+/// Read a file, or standard input when the path is "-"
+fn read_input(path: &PathBuf) -> Result<String, Box<dyn std::error::Error>> {
+    if path.as_os_str() == "-" {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        Ok(input)
+    } else {
+        Ok(std::fs::read_to_string(path)?)
+    }
+}
+
+pub fn run(
+    command: Command,
+    verbs: &[Verb],
+    templates: &HashMap<String, Template>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::Induce { paradigm, data } => {
+            let paradigm: Template = serde_json::from_str(&read_input(&paradigm)?)?;
+            let induction = induction::induce(&paradigm, templates)?;
+            if let Some(directory) = data {
+                let verbs_path = directory.join("verbs-fr.xml");
+                let conjugation_path = directory.join("conjugation-fr.xml");
+                let (verbs_xml, conjugation_xml) = induction::add_to_data(
+                    &induction,
+                    &std::fs::read_to_string(&verbs_path)?,
+                    &std::fs::read_to_string(&conjugation_path)?,
+                )?;
+                if let Some(conjugation_xml) = conjugation_xml {
+                    std::fs::write(&conjugation_path, conjugation_xml)?;
+                    eprintln!("Added template to {}", conjugation_path.display());
+                }
+                std::fs::write(&verbs_path, verbs_xml)?;
+                eprintln!("Added {} to {}", induction.infinitive, verbs_path.display());
+            }
+            if induction.matches.is_empty() {
+                eprintln!(
+                    "No existing template generates the forms of {}; proposed template:",
+                    induction.infinitive
+                );
+                print!("{}", induction.xml.unwrap_or_default());
+            } else {
+                println!(
+                    "{} is generated by existing template(s): {}",
+                    induction.infinitive,
+                    induction.matches.join(", ")
+                );
+            }
+        }
+        Command::AnalyzeTemplates {
+            max_differences,
+            json,
+        } => {
+            let report = analysis::analyze_templates(templates, verbs, max_differences);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print!("{}", analysis::format_report(&report));
+            }
+        }
+        Command::Worksheet {
+            verbs: verb_list,
+            template,
+            group,
+            tenses,
+            count,
+            seed,
+            title,
+            format,
+            output,
+        } => {
+            let query = WorksheetQuery {
+                verbs: verb_list,
+                template,
+                group,
+                tenses: Some(tenses),
+                count: Some(count),
+                seed,
+                title,
+                format,
+            };
+            let sheet = worksheet::generate(verbs, templates, &query)?;
+            let document = match format {
+                WorksheetFormat::Html => worksheet::render_html(&sheet).into_bytes(),
+                WorksheetFormat::Pdf => worksheet::render_pdf(&sheet),
+            };
+            if output.as_os_str() == "-" {
+                std::io::stdout().write_all(&document)?;
+            } else {
+                std::fs::write(&output, document)?;
+                eprintln!(
+                    "Worksheet written to {} (seed {})",
+                    output.display(),
+                    sheet.seed
+                );
+            }
+        }
+        Command::Anki {
+            verbs: verb_list,
+            template,
+            group,
+            tenses,
+            per_person,
+            deck,
+            front,
+            back,
+            output,
+        } => {
+            let selected = match verb_list {
+                Some(names) => drill::named(verbs, &names)?,
+                None => drill::candidates(verbs, templates, template.as_deref(), group),
+            };
+            let options = AnkiOptions {
+                deck,
+                per_person,
+                tenses: drill::parse_tenses(tenses.as_deref())?,
+                front,
+                back,
+            };
+            let count = anki::export(&selected, templates, &options, &output)?;
+            eprintln!("{} notes written to {}", count, output.display());
+        }
+        Command::Export { format, output } => {
+            if output.as_os_str() == "-" {
+                let stdout = std::io::stdout().lock();
+                export::write_table(verbs, templates, format, true, BufWriter::new(stdout))?;
+            } else {
+                let file = std::fs::File::create(&output)?;
+                let rows =
+                    export::write_table(verbs, templates, format, true, BufWriter::new(file))?;
+                eprintln!("{} rows written to {}", rows, output.display());
+            }
+        }
+        Command::Sqlite { output } => {
+            let counts = dataset::export(verbs, templates, &output)?;
+            eprintln!(
+                "{} verbs, {} templates ({} cells) and {} forms written to {}",
+                counts.verbs,
+                counts.templates,
+                counts.template_cells,
+                counts.forms,
+                output.display()
+            );
+        }
+        Command::Hunspell { output } => {
+            let dictionary = hunspell::generate(verbs, templates);
+            std::fs::write(output.with_extension("aff"), dictionary.aff)?;
+            std::fs::write(output.with_extension("dic"), dictionary.dic)?;
+            eprintln!(
+                "Hunspell dictionary written to {} and {}",
+                output.with_extension("aff").display(),
+                output.with_extension("dic").display()
+            );
+        }
+        Command::Dictionary { format, output } => match format {
+            DictionaryFormat::Stardict => {
+                let dictionary = dictionary::stardict(verbs, templates);
+                std::fs::write(output.with_extension("ifo"), dictionary.ifo)?;
+                std::fs::write(output.with_extension("idx"), dictionary.idx)?;
+                std::fs::write(output.with_extension("dict"), dictionary.dict)?;
+                std::fs::write(output.with_extension("syn"), dictionary.syn)?;
+                eprintln!(
+                    "StarDict dictionary written to {}",
+                    output.with_extension("ifo").display()
+                );
+            }
+            DictionaryFormat::Kindle => {
+                let count = dictionary::kindle(verbs, templates, &output)?;
+                eprintln!("{} entries written to {}", count, output.display());
+            }
+        },
+        Command::Morphology { format, output } => {
+            if output.as_os_str() == "-" {
+                let stdout = std::io::stdout().lock();
+                morphology::write(verbs, templates, format, BufWriter::new(stdout))?;
+            } else {
+                let file = std::fs::File::create(&output)?;
+                let count = morphology::write(verbs, templates, format, BufWriter::new(file))?;
+                eprintln!("{} forms written to {}", count, output.display());
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,1519 @@
+use crate::pronouns::{self, PronounSet};
+use crate::properties::{self, VerbProperties};
+use crate::sentence::{Phrase, SentenceForm, Subject};
+use crate::spelling::{self, Spelling};
+use crate::template::Template;
+use crate::verbs::Verb;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Verbs conjugated with "être" in compound tenses (when used intransitively)
+const ETRE_VERBS: &[&str] = &[
+    "advenir",
+    "aller",
+    "apparaître",
+    "arriver",
+    "convenir",
+    "décéder",
+    "descendre",
+    "devenir",
+    "disparaître",
+    "échoir",
+    "éclore",
+    "entrer",
+    "expirer",
+    "intervenir",
+    "monter",
+    "mourir",
+    "naître",
+    "partir",
+    "parvenir",
+    "passer",
+    "provenir",
+    "redescendre",
+    "redevenir",
+    "remonter",
+    "renaître",
+    "rentrer",
+    "repartir",
+    "ressortir",
+    "ressusciter",
+    "rester",
+    "retomber",
+    "retourner",
+    "revenir",
+    "sortir",
+    "subvenir",
+    "survenir",
+    "tomber",
+    "venir",
+];
+
+/// Verbs taking a direct object, the only ones with a passive voice ("la pomme
+/// est mangée"). This is a whitelist: verbs used both ways ("monter les
+/// valises") are listed, verbs missing from it are treated as intransitive, and
+/// verbs derived from a listed one ("redécouvrir") follow their base.
+const TRANSITIVE_VERBS: &[&str] = &[
+    "abandonner",
+    "abattre",
+    "abîmer",
+    "abolir",
+    "aborder",
+    "absorber",
+    "accabler",
+    "accepter",
+    "accompagner",
+    "accomplir",
+    "accorder",
+    "accrocher",
+    "accueillir",
+    "accumuler",
+    "accuser",
+    "acheter",
+    "achever",
+    "acquérir",
+    "adapter",
+    "admettre",
+    "administrer",
+    "admirer",
+    "adopter",
+    "adorer",
+    "adresser",
+    "affecter",
+    "affirmer",
+    "affronter",
+    "agacer",
+    "agrandir",
+    "aider",
+    "aimer",
+    "ajouter",
+    "ajuster",
+    "alimenter",
+    "allumer",
+    "amener",
+    "amuser",
+    "analyser",
+    "animer",
+    "annoncer",
+    "annuler",
+    "apercevoir",
+    "appeler",
+    "applaudir",
+    "appliquer",
+    "apporter",
+    "apprécier",
+    "apprendre",
+    "approuver",
+    "arracher",
+    "arranger",
+    "arrêter",
+    "arroser",
+    "assassiner",
+    "assembler",
+    "assurer",
+    "attacher",
+    "attaquer",
+    "atteindre",
+    "attendre",
+    "attirer",
+    "attraper",
+    "augmenter",
+    "autoriser",
+    "avaler",
+    "avertir",
+    "bâtir",
+    "battre",
+    "bénir",
+    "blâmer",
+    "blesser",
+    "boire",
+    "boucher",
+    "bouger",
+    "bousculer",
+    "briser",
+    "brosser",
+    "brûler",
+    "cacher",
+    "calculer",
+    "calmer",
+    "casser",
+    "causer",
+    "célébrer",
+    "censurer",
+    "chanter",
+    "charger",
+    "chasser",
+    "chauffer",
+    "chercher",
+    "choisir",
+    "citer",
+    "classer",
+    "coiffer",
+    "coller",
+    "combattre",
+    "commander",
+    "commencer",
+    "commettre",
+    "communiquer",
+    "comparer",
+    "composer",
+    "comprendre",
+    "compter",
+    "concevoir",
+    "condamner",
+    "conduire",
+    "confier",
+    "confirmer",
+    "connaître",
+    "conquérir",
+    "consacrer",
+    "conseiller",
+    "conserver",
+    "considérer",
+    "consoler",
+    "construire",
+    "consulter",
+    "contenir",
+    "contester",
+    "continuer",
+    "contredire",
+    "contrôler",
+    "convaincre",
+    "convoquer",
+    "copier",
+    "corriger",
+    "couper",
+    "courir",
+    "couvrir",
+    "craindre",
+    "créer",
+    "creuser",
+    "critiquer",
+    "croire",
+    "cueillir",
+    "cuire",
+    "cultiver",
+    "décevoir",
+    "décider",
+    "déclarer",
+    "décorer",
+    "découper",
+    "découvrir",
+    "décrire",
+    "défendre",
+    "définir",
+    "déguster",
+    "demander",
+    "déménager",
+    "démolir",
+    "dénoncer",
+    "déplacer",
+    "déposer",
+    "déranger",
+    "descendre",
+    "dessiner",
+    "détester",
+    "détruire",
+    "développer",
+    "deviner",
+    "dévorer",
+    "diriger",
+    "discuter",
+    "distribuer",
+    "diviser",
+    "dominer",
+    "donner",
+    "dresser",
+    "éclairer",
+    "écouter",
+    "écraser",
+    "écrire",
+    "effacer",
+    "effrayer",
+    "élever",
+    "élire",
+    "embrasser",
+    "emmener",
+    "employer",
+    "emporter",
+    "emprunter",
+    "encourager",
+    "endormir",
+    "enfermer",
+    "enlever",
+    "ennuyer",
+    "enregistrer",
+    "enseigner",
+    "entendre",
+    "enterrer",
+    "entourer",
+    "entraîner",
+    "entretenir",
+    "envahir",
+    "envoyer",
+    "épouser",
+    "essayer",
+    "essuyer",
+    "estimer",
+    "éteindre",
+    "étendre",
+    "étonner",
+    "étudier",
+    "éviter",
+    "examiner",
+    "exciter",
+    "excuser",
+    "exécuter",
+    "exiger",
+    "expliquer",
+    "exploiter",
+    "exposer",
+    "exprimer",
+    "fabriquer",
+    "fâcher",
+    "faire",
+    "fermer",
+    "fêter",
+    "financer",
+    "finir",
+    "fixer",
+    "fonder",
+    "forcer",
+    "former",
+    "fouiller",
+    "fournir",
+    "franchir",
+    "frapper",
+    "fumer",
+    "gagner",
+    "garder",
+    "garer",
+    "gaspiller",
+    "gâter",
+    "geler",
+    "gérer",
+    "glisser",
+    "goûter",
+    "gouverner",
+    "gratter",
+    "guérir",
+    "guider",
+    "habiller",
+    "habiter",
+    "haïr",
+    "heurter",
+    "honorer",
+    "ignorer",
+    "illuminer",
+    "imaginer",
+    "imiter",
+    "imposer",
+    "imprimer",
+    "informer",
+    "inquiéter",
+    "inscrire",
+    "inspecter",
+    "installer",
+    "interdire",
+    "interroger",
+    "interrompre",
+    "inventer",
+    "inviter",
+    "jeter",
+    "joindre",
+    "juger",
+    "laisser",
+    "lancer",
+    "laver",
+    "lever",
+    "libérer",
+    "lier",
+    "lire",
+    "livrer",
+    "louer",
+    "manger",
+    "manier",
+    "manquer",
+    "maquiller",
+    "marquer",
+    "menacer",
+    "mener",
+    "mépriser",
+    "mesurer",
+    "mettre",
+    "modifier",
+    "monter",
+    "montrer",
+    "mordre",
+    "moudre",
+    "multiplier",
+    "nettoyer",
+    "nommer",
+    "nourrir",
+    "obliger",
+    "observer",
+    "obtenir",
+    "occuper",
+    "offrir",
+    "oublier",
+    "ouvrir",
+    "partager",
+    "passer",
+    "payer",
+    "peigner",
+    "peindre",
+    "percer",
+    "perdre",
+    "permettre",
+    "persuader",
+    "peser",
+    "photographier",
+    "placer",
+    "planter",
+    "plier",
+    "plonger",
+    "porter",
+    "poser",
+    "posséder",
+    "pousser",
+    "pratiquer",
+    "préférer",
+    "prendre",
+    "préparer",
+    "présenter",
+    "préserver",
+    "presser",
+    "prêter",
+    "prévenir",
+    "prévoir",
+    "prier",
+    "produire",
+    "promettre",
+    "prononcer",
+    "proposer",
+    "protéger",
+    "prouver",
+    "publier",
+    "punir",
+    "quitter",
+    "raconter",
+    "ramasser",
+    "ramener",
+    "ranger",
+    "rappeler",
+    "rassurer",
+    "rater",
+    "ravir",
+    "recevoir",
+    "rechercher",
+    "recommander",
+    "reconnaître",
+    "recueillir",
+    "redécouvrir",
+    "réduire",
+    "refuser",
+    "regarder",
+    "régler",
+    "regretter",
+    "rejeter",
+    "rejoindre",
+    "remarquer",
+    "rembourser",
+    "remercier",
+    "remettre",
+    "remonter",
+    "remplacer",
+    "remplir",
+    "remporter",
+    "rencontrer",
+    "rendre",
+    "rentrer",
+    "renverser",
+    "réparer",
+    "répéter",
+    "reprendre",
+    "représenter",
+    "reprocher",
+    "réserver",
+    "résoudre",
+    "respecter",
+    "respirer",
+    "retenir",
+    "retirer",
+    "retourner",
+    "retrouver",
+    "réunir",
+    "réussir",
+    "réveiller",
+    "révéler",
+    "revoir",
+    "rompre",
+    "saisir",
+    "saluer",
+    "satisfaire",
+    "sauver",
+    "savoir",
+    "secouer",
+    "séduire",
+    "sélectionner",
+    "semer",
+    "sentir",
+    "séparer",
+    "serrer",
+    "servir",
+    "signaler",
+    "signer",
+    "soigner",
+    "sortir",
+    "souffrir",
+    "souhaiter",
+    "soulever",
+    "souligner",
+    "soumettre",
+    "soupçonner",
+    "soutenir",
+    "subir",
+    "suggérer",
+    "suivre",
+    "supporter",
+    "supposer",
+    "supprimer",
+    "surprendre",
+    "surveiller",
+    "tailler",
+    "taper",
+    "teindre",
+    "tendre",
+    "tenir",
+    "terminer",
+    "tirer",
+    "tolérer",
+    "toucher",
+    "tourner",
+    "traduire",
+    "trahir",
+    "traiter",
+    "transformer",
+    "transmettre",
+    "transporter",
+    "traverser",
+    "trouver",
+    "tuer",
+    "unir",
+    "user",
+    "utiliser",
+    "vaincre",
+    "vendre",
+    "vérifier",
+    "verser",
+    "visiter",
+    "voir",
+    "voler",
+    "vouloir",
+];
+
+/// Heading of a kebab-case name: words separated by spaces, the first one
+/// capitalized ("indicative-present" -> "Indicative present")
+fn heading(name: &str) -> String {
+    let name = name.replace('-', " ");
+    let mut chars = name.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// Grammatical mood, mirroring the sections of a conjugation template
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mood {
+    Infinitive,
+    Indicative,
+    Conditional,
+    Subjunctive,
+    Imperative,
+    Participle,
+    Gerund,
+}
+
+impl Mood {
+    /// Name of the mood as used in the API
+    pub fn name(self) -> &'static str {
+        match self {
+            Mood::Infinitive => "infinitive",
+            Mood::Indicative => "indicative",
+            Mood::Conditional => "conditional",
+            Mood::Subjunctive => "subjunctive",
+            Mood::Imperative => "imperative",
+            Mood::Participle => "participle",
+            Mood::Gerund => "gerund",
+        }
+    }
+
+    /// Name of the mood for headings ("Indicative")
+    pub fn label(self) -> String {
+        heading(self.name())
+    }
+}
+
+/// Tense of a conjugation. Simple tenses are named after their XML section in
+/// conjugation-fr.xml; compound tenses combine an auxiliary and the past participle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Tense {
+    InfinitivePresent,
+    IndicativePresent,
+    IndicativeImperfect,
+    IndicativeFuture,
+    IndicativeSimplePast,
+    /// Passé composé
+    IndicativePerfect,
+    /// Plus-que-parfait
+    IndicativePluperfect,
+    /// Passé antérieur
+    IndicativePastAnterior,
+    /// Futur antérieur
+    IndicativeFuturePerfect,
+    ConditionalPresent,
+    ConditionalPast,
+    SubjunctivePresent,
+    SubjunctiveImperfect,
+    SubjunctivePast,
+    SubjunctivePluperfect,
+    ImperativePresent,
+    ImperativePast,
+    PresentParticiple,
+    PastParticiple,
+    /// Infinitif passé: "avoir mangé", "être parti"
+    InfinitivePast,
+    /// Gérondif: "en mangeant"
+    Gerund,
+}
+
+impl Tense {
+    /// Tenses stored in templates, in the order they appear in conjugation-fr.xml
+    pub const SIMPLE: [Tense; 11] = [
+        Tense::InfinitivePresent,
+        Tense::IndicativePresent,
+        Tense::IndicativeImperfect,
+        Tense::IndicativeFuture,
+        Tense::IndicativeSimplePast,
+        Tense::ConditionalPresent,
+        Tense::SubjunctivePresent,
+        Tense::SubjunctiveImperfect,
+        Tense::ImperativePresent,
+        Tense::PresentParticiple,
+        Tense::PastParticiple,
+    ];
+
+    /// All tenses, grouped by mood
+    pub const ALL: [Tense; 19] = [
+        Tense::InfinitivePresent,
+        Tense::IndicativePresent,
+        Tense::IndicativeImperfect,
+        Tense::IndicativeFuture,
+        Tense::IndicativeSimplePast,
+        Tense::IndicativePerfect,
+        Tense::IndicativePluperfect,
+        Tense::IndicativePastAnterior,
+        Tense::IndicativeFuturePerfect,
+        Tense::ConditionalPresent,
+        Tense::ConditionalPast,
+        Tense::SubjunctivePresent,
+        Tense::SubjunctiveImperfect,
+        Tense::SubjunctivePast,
+        Tense::SubjunctivePluperfect,
+        Tense::ImperativePresent,
+        Tense::ImperativePast,
+        Tense::PresentParticiple,
+        Tense::PastParticiple,
+    ];
+
+    /// Tenses derived from the participles and the auxiliary, returned in their own
+    /// section of a conjugation
+    pub const DERIVED: [Tense; 2] = [Tense::InfinitivePast, Tense::Gerund];
+
+    /// Name of the tense as used in the API and in conjugation-fr.xml sections
+    pub fn name(self) -> &'static str {
+        match self {
+            Tense::InfinitivePresent => "infinitive-present",
+            Tense::IndicativePresent => "indicative-present",
+            Tense::IndicativeImperfect => "indicative-imperfect",
+            Tense::IndicativeFuture => "indicative-future",
+            Tense::IndicativeSimplePast => "indicative-simple-past",
+            Tense::IndicativePerfect => "indicative-perfect",
+            Tense::IndicativePluperfect => "indicative-pluperfect",
+            Tense::IndicativePastAnterior => "indicative-past-anterior",
+            Tense::IndicativeFuturePerfect => "indicative-future-perfect",
+            Tense::ConditionalPresent => "conditional-present",
+            Tense::ConditionalPast => "conditional-past",
+            Tense::SubjunctivePresent => "subjunctive-present",
+            Tense::SubjunctiveImperfect => "subjunctive-imperfect",
+            Tense::SubjunctivePast => "subjunctive-past",
+            Tense::SubjunctivePluperfect => "subjunctive-pluperfect",
+            Tense::ImperativePresent => "imperative-present",
+            Tense::ImperativePast => "imperative-past",
+            Tense::PresentParticiple => "present-participle",
+            Tense::PastParticiple => "past-participle",
+            Tense::InfinitivePast => "infinitive-past",
+            Tense::Gerund => "gerund",
+        }
+    }
+
+    /// Name of the tense for headings ("indicative-present" -> "Indicative present")
+    pub fn label(self) -> String {
+        heading(self.name())
+    }
+
+    pub fn mood(self) -> Mood {
+        match self {
+            Tense::InfinitivePresent | Tense::InfinitivePast => Mood::Infinitive,
+            Tense::IndicativePresent
+            | Tense::IndicativeImperfect
+            | Tense::IndicativeFuture
+            | Tense::IndicativeSimplePast
+            | Tense::IndicativePerfect
+            | Tense::IndicativePluperfect
+            | Tense::IndicativePastAnterior
+            | Tense::IndicativeFuturePerfect => Mood::Indicative,
+            Tense::ConditionalPresent | Tense::ConditionalPast => Mood::Conditional,
+            Tense::SubjunctivePresent
+            | Tense::SubjunctiveImperfect
+            | Tense::SubjunctivePast
+            | Tense::SubjunctivePluperfect => Mood::Subjunctive,
+            Tense::ImperativePresent | Tense::ImperativePast => Mood::Imperative,
+            Tense::PresentParticiple | Tense::PastParticiple => Mood::Participle,
+            Tense::Gerund => Mood::Gerund,
+        }
+    }
+
+    /// Tense of the auxiliary for a compound tense; `None` for simple tenses
+    pub fn auxiliary_tense(self) -> Option<Tense> {
+        match self {
+            Tense::IndicativePerfect => Some(Tense::IndicativePresent),
+            Tense::IndicativePluperfect => Some(Tense::IndicativeImperfect),
+            Tense::IndicativePastAnterior => Some(Tense::IndicativeSimplePast),
+            Tense::IndicativeFuturePerfect => Some(Tense::IndicativeFuture),
+            Tense::ConditionalPast => Some(Tense::ConditionalPresent),
+            Tense::SubjunctivePast => Some(Tense::SubjunctivePresent),
+            Tense::SubjunctivePluperfect => Some(Tense::SubjunctiveImperfect),
+            Tense::ImperativePast => Some(Tense::ImperativePresent),
+            _ => None,
+        }
+    }
+
+    /// Person of each cell of the tense; `None` for non-finite tenses
+    pub fn persons(self) -> Option<&'static [Person]> {
+        match self.mood() {
+            Mood::Indicative | Mood::Conditional | Mood::Subjunctive => Some(&Person::ALL),
+            Mood::Imperative => Some(&Person::IMPERATIVE),
+            Mood::Infinitive | Mood::Participle | Mood::Gerund => None,
+        }
+    }
+}
+
+/// Grammatical person of a conjugated form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Person {
+    FirstSingular,
+    SecondSingular,
+    ThirdSingular,
+    FirstPlural,
+    SecondPlural,
+    ThirdPlural,
+}
+
+impl Person {
+    /// Persons in the order of the cells of a six-cell tense
+    pub const ALL: [Person; 6] = [
+        Person::FirstSingular,
+        Person::SecondSingular,
+        Person::ThirdSingular,
+        Person::FirstPlural,
+        Person::SecondPlural,
+        Person::ThirdPlural,
+    ];
+
+    /// Persons in the order of the cells of the imperative
+    pub const IMPERATIVE: [Person; 3] = [
+        Person::SecondSingular,
+        Person::FirstPlural,
+        Person::SecondPlural,
+    ];
+
+    /// Name of the person as used in the API
+    pub fn name(self) -> &'static str {
+        match self {
+            Person::FirstSingular => "first-singular",
+            Person::SecondSingular => "second-singular",
+            Person::ThirdSingular => "third-singular",
+            Person::FirstPlural => "first-plural",
+            Person::SecondPlural => "second-plural",
+            Person::ThirdPlural => "third-plural",
+        }
+    }
+
+    pub fn number(self) -> Number {
+        match self {
+            Person::FirstSingular | Person::SecondSingular | Person::ThirdSingular => {
+                Number::Singular
+            }
+            Person::FirstPlural | Person::SecondPlural | Person::ThirdPlural => Number::Plural,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Gender {
+    #[default]
+    Masculine,
+    Feminine,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Number {
+    #[default]
+    Singular,
+    Plural,
+}
+
+/// Index of the past participle cell agreeing in gender and number
+/// (cells are ordered masc. sing., masc. pl., fem. sing., fem. pl.)
+pub fn past_participle_index(gender: Gender, number: Number) -> usize {
+    match (gender, number) {
+        (Gender::Masculine, Number::Singular) => 0,
+        (Gender::Masculine, Number::Plural) => 1,
+        (Gender::Feminine, Number::Singular) => 2,
+        (Gender::Feminine, Number::Plural) => 3,
+    }
+}
+
+/// Auxiliary verb of compound tenses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Auxiliary {
+    #[serde(rename = "avoir")]
+    Avoir,
+    #[serde(rename = "être", alias = "etre")]
+    Etre,
+}
+
+impl Auxiliary {
+    /// Dictionary entry of the auxiliary
+    pub fn verb(self) -> Verb {
+        let (verb, template) = match self {
+            Auxiliary::Avoir => ("avoir", ":avoir"),
+            Auxiliary::Etre => ("être", ":être"),
+        };
+        Verb {
+            verb: verb.to_string(),
+            template: template.to_string(),
+            aspirate_h: false,
+        }
+    }
+}
+
+/// Voice of a conjugation: active ("il aime") or passive ("il est aimé")
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Voice {
+    #[default]
+    Active,
+    Passive,
+}
+
+/// Why a verb has no passive voice, if it has none. Derived verbs are checked
+/// through their base (see `ConjugationOptions::derived_from`).
+pub fn passive_restriction(verb: &str, pronominal: bool) -> Option<&'static str> {
+    if pronominal {
+        Some("pronominal verbs have no passive voice")
+    } else if properties::is_impersonal(verb) {
+        Some("impersonal verbs have no passive voice")
+    } else if !TRANSITIVE_VERBS.contains(&verb) {
+        Some("only verbs on the list of verbs taking a direct object have a passive voice")
+    } else {
+        None
+    }
+}
+
+/// Auxiliary used by a verb in compound tenses; pronominal verbs always take "être"
+pub fn auxiliary(verb: &str, pronominal: bool) -> Auxiliary {
+    if pronominal || ETRE_VERBS.contains(&verb) {
+        Auxiliary::Etre
+    } else {
+        Auxiliary::Avoir
+    }
+}
+
+/// Endings of one simple tense of a template, one entry per cell.
+/// Single-cell sections (infinitive, present participle) are wrapped into one cell;
+/// compound tenses have no endings.
+pub fn endings(template: &Template, tense: Tense) -> Vec<Vec<String>> {
+    match tense {
+        Tense::InfinitivePresent => vec![template.infinitive.infinitive_present.clone()],
+        Tense::IndicativePresent => template.indicative.present.clone(),
+        Tense::IndicativeImperfect => template.indicative.imperfect.clone(),
+        Tense::IndicativeFuture => template.indicative.future.clone(),
+        Tense::IndicativeSimplePast => template.indicative.simple_past.clone(),
+        Tense::ConditionalPresent => template.conditional.present.clone(),
+        Tense::SubjunctivePresent => template.subjunctive.present.clone(),
+        Tense::SubjunctiveImperfect => template.subjunctive.imperfect.clone(),
+        Tense::ImperativePresent => template.imperative.imperative_present.clone(),
+        Tense::PresentParticiple => vec![template.participle.present_participle.clone()],
+        Tense::PastParticiple => template.participle.past_participle.clone(),
+        _ => Vec::new(),
+    }
+}
+
+/// Termination of a template name, i.e. the part after the colon ("aim:er" -> "er")
+pub fn template_suffix(template_name: &str) -> &str {
+    template_name
+        .split_once(':')
+        .map(|(_, suffix)| suffix)
+        .unwrap_or(template_name)
+}
+
+/// Stem of a verb for the given template ("aimer" with "aim:er" -> "aim")
+pub fn stem<'a>(verb: &'a str, template_name: &str) -> Option<&'a str> {
+    verb.strip_suffix(template_suffix(template_name))
+}
+
+/// Bare forms of every simple tense of a verb: tense -> cells -> variants.
+/// Missing forms give empty cells.
+pub fn simple_forms(verb: &Verb, template: &Template) -> Option<HashMap<Tense, Vec<Vec<String>>>> {
+    let stem = stem(&verb.verb, &template.name)?;
+    Some(
+        Tense::SIMPLE
+            .iter()
+            .map(|&tense| {
+                let cells = endings(template, tense)
+                    .iter()
+                    .map(|cell| {
+                        cell.iter()
+                            .map(|ending| format!("{stem}{ending}"))
+                            .collect()
+                    })
+                    .collect();
+                (tense, cells)
+            })
+            .collect(),
+    )
+}
+
+/// Why a cell of a conjugation table has no form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MissingReason {
+    /// Impersonal verbs only exist in the third person singular
+    Impersonal,
+    /// The template has no form for this cell
+    Defective,
+    /// Pronominal verbs have no past imperative
+    Pronominal,
+}
+
+/// Forms of a cell following the 1990 spelling reform, when they differ from
+/// the traditional ones
+#[derive(Debug, Clone, Serialize)]
+pub struct ReformedVariants {
+    pub forms: Vec<String>,
+    pub display: Vec<String>,
+}
+
+/// One cell of a conjugation table: the bare forms (several when the template
+/// lists variants) and the same forms as displayed, with their subject pronoun.
+/// Forms are `null` when the cell does not exist, with the reason in `missing`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Cell {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub person: Option<Person>,
+    pub forms: Option<Vec<String>>,
+    pub display: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReason>,
+    /// Which of `forms` and `display` are 1990 spellings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reformed: Option<ReformedVariants>,
+}
+
+impl Cell {
+    /// Cell from forms and display strings, each flagged when it is a 1990 spelling
+    fn new(
+        person: Option<Person>,
+        forms: Vec<(String, bool)>,
+        display: Vec<(String, bool)>,
+    ) -> Cell {
+        let tagged = |strings: &[(String, bool)]| -> Vec<String> {
+            strings
+                .iter()
+                .filter(|(_, reformed)| *reformed)
+                .map(|(string, _)| string.clone())
+                .collect()
+        };
+        let reformed = ReformedVariants {
+            forms: tagged(&forms),
+            display: tagged(&display),
+        };
+        Cell {
+            person,
+            forms: Some(forms.into_iter().map(|(form, _)| form).collect()),
+            display: Some(display.into_iter().map(|(display, _)| display).collect()),
+            missing: None,
+            reformed: (!reformed.forms.is_empty()).then_some(reformed),
+        }
+    }
+
+    fn missing(person: Option<Person>, reason: MissingReason) -> Cell {
+        Cell {
+            person,
+            forms: None,
+            display: None,
+            missing: Some(reason),
+            reformed: None,
+        }
+    }
+}
+
+/// Conjugated forms of one tense
+#[derive(Debug, Clone, Serialize)]
+pub struct TenseTable {
+    pub mood: Mood,
+    pub tense: Tense,
+    pub cells: Vec<Cell>,
+}
+
+/// Full conjugation of a verb, computed from its template
+#[derive(Debug, Clone, Serialize)]
+pub struct Conjugation {
+    pub verb: String,
+    pub template: String,
+    pub aspirate_h: bool,
+    #[serde(flatten)]
+    pub properties: VerbProperties,
+    pub pronominal: bool,
+    pub spelling: Spelling,
+    /// Auxiliary of the compound tenses, "avoir" in the passive voice ("j'ai été aimé")
+    pub auxiliary: Auxiliary,
+    pub voice: Voice,
+    pub form: SentenceForm,
+    pub tenses: Vec<TenseTable>,
+    /// Gérondif and infinitif passé, built from the participles and the auxiliary
+    pub derived: Vec<TenseTable>,
+}
+
+/// Rendering options of a conjugation, read from the query string
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ConjugationOptions {
+    pub pronouns: PronounSet,
+    /// Conjugate the verb with a reflexive pronoun ("se lever")
+    pub pronominal: bool,
+    /// Gender of subjects whose pronoun does not carry one (je, tu, nous, vous, on),
+    /// used for past participle agreement
+    pub gender: Gender,
+    /// Number used for agreement where no subject gives one (passive infinitive
+    /// and present participle)
+    pub number: Number,
+    pub voice: Voice,
+    /// Negative and interrogative forms of the displayed phrases
+    pub form: SentenceForm,
+    /// Traditional or 1990 spelling
+    pub spelling: Spelling,
+    /// Listed verb a derived verb was formed from ("découvrir" for
+    /// "redécouvrir"), whose transitivity decides the passive voice. Set from
+    /// the resolution of the verb, never from the query string.
+    #[serde(skip)]
+    pub derived_from: Option<String>,
+}
+
+impl ConjugationOptions {
+    /// Why `verb` has no passive voice with these options, if it has none
+    pub fn passive_restriction(&self, verb: &str) -> Option<&'static str> {
+        passive_restriction(
+            self.derived_from.as_deref().unwrap_or(verb),
+            self.pronominal,
+        )
+    }
+}
+
+/// Forms of a verb and of its auxiliary, from which every cell is rendered.
+/// In the passive voice the conjugated verb is "être", followed by the past
+/// participle of the requested verb.
+struct Conjugator<'a> {
+    verb: Verb,
+    /// Whether the requested verb is impersonal
+    impersonal: bool,
+    options: &'a ConjugationOptions,
+    auxiliary: Auxiliary,
+    forms: HashMap<Tense, Vec<Vec<String>>>,
+    auxiliary_forms: HashMap<Tense, Vec<Vec<String>>>,
+    passive_participles: Option<Vec<Vec<String>>>,
+    /// Forms of the verb that are 1990 spellings
+    reformed: HashSet<String>,
+}
+
+impl Conjugator<'_> {
+    fn simple_cell(
+        forms: &HashMap<Tense, Vec<Vec<String>>>,
+        tense: Tense,
+        index: usize,
+    ) -> Vec<String> {
+        forms
+            .get(&tense)
+            .and_then(|cells| cells.get(index))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Past participle, agreeing with the subject when the auxiliary is "être"
+    fn participles(&self, gender: Gender, number: Number) -> Vec<String> {
+        let index = match self.auxiliary {
+            Auxiliary::Etre => past_participle_index(gender, number),
+            Auxiliary::Avoir => 0,
+        };
+        Self::simple_cell(&self.forms, Tense::PastParticiple, index)
+    }
+
+    /// Whether a phrase is built on a 1990 spelling of the verb
+    fn is_reformed(&self, phrase: &Phrase) -> bool {
+        phrase.finite_is_verb && self.reformed.contains(&phrase.finite)
+    }
+
+    /// In the passive voice, follow each phrase with the agreeing past participle
+    /// of the verb ("suis" -> "suis aimée", "ai été" -> "ai été aimée")
+    fn passive(&self, phrases: Vec<Phrase>, gender: Gender, number: Number) -> Vec<Phrase> {
+        let Some(cells) = &self.passive_participles else {
+            return phrases;
+        };
+        let participles = cells
+            .get(past_participle_index(gender, number))
+            .cloned()
+            .unwrap_or_default();
+        phrases
+            .iter()
+            .flat_map(|phrase| {
+                participles.iter().map(move |participle| Phrase {
+                    finite_is_verb: false,
+                    participle: Some(match &phrase.participle {
+                        Some(auxiliary_participle) => {
+                            format!("{auxiliary_participle} {participle}")
+                        }
+                        None => participle.clone(),
+                    }),
+                    ..phrase.clone()
+                })
+            })
+            .collect()
+    }
+
+    /// Verb phrases of a finite cell, without the subject
+    fn phrases(
+        &self,
+        tense: Tense,
+        index: usize,
+        person: Person,
+        gender: Gender,
+        number: Number,
+        negative: bool,
+    ) -> Vec<Phrase> {
+        let phrases: Vec<Phrase> = match tense.auxiliary_tense() {
+            None => Self::simple_cell(&self.forms, tense, index)
+                .into_iter()
+                .map(|form| Phrase {
+                    reflexive: None,
+                    finite: form,
+                    finite_is_verb: true,
+                    stressed: None,
+                    participle: None,
+                })
+                .collect(),
+            Some(auxiliary_tense) => {
+                let participles = self.participles(gender, number);
+                Self::simple_cell(&self.auxiliary_forms, auxiliary_tense, index)
+                    .iter()
+                    .flat_map(|auxiliary| {
+                        participles.iter().map(move |participle| Phrase {
+                            reflexive: None,
+                            finite: auxiliary.clone(),
+                            finite_is_verb: false,
+                            stressed: None,
+                            participle: Some(participle.clone()),
+                        })
+                    })
+                    .collect()
+            }
+        };
+        let phrases = self.passive(phrases, gender, number);
+
+        if !self.options.pronominal {
+            return phrases;
+        }
+        match tense {
+            // Affirmative imperative takes a hyphenated stressed pronoun ("lève-toi"),
+            // negative imperative the usual one ("ne te lève pas")
+            Tense::ImperativePresent if !negative => phrases
+                .into_iter()
+                .map(|phrase| Phrase {
+                    stressed: Some(pronouns::stressed_reflexive_pronoun(person)),
+                    ..phrase
+                })
+                .collect(),
+            // Pronominal verbs have no past imperative
+            Tense::ImperativePast => Vec::new(),
+            _ => phrases
+                .into_iter()
+                .map(|phrase| Phrase {
+                    reflexive: Some(pronouns::reflexive_pronoun(person)),
+                    ..phrase
+                })
+                .collect(),
+        }
+    }
+
+    /// Cell of a finite tense, with one display string per subject pronoun and form
+    fn finite_cell(&self, tense: Tense, person: Person) -> Cell {
+        let pronoun_set = self.options.pronouns;
+        // The pronoun set may borrow the forms of another person (formal "vous")
+        let verb_person = pronoun_set.verb_person(person);
+        let index = tense
+            .persons()
+            .and_then(|persons| persons.iter().position(|&p| p == verb_person))
+            .unwrap_or_default();
+        // Agreement follows the addressed person: formal "vous" is singular
+        let number = person.number();
+
+        if self.impersonal && person != Person::ThirdSingular {
+            return Cell::missing(Some(person), MissingReason::Impersonal);
+        }
+        let phrases = self.phrases(
+            tense,
+            index,
+            verb_person,
+            self.options.gender,
+            number,
+            false,
+        );
+        if phrases.is_empty() {
+            let reason = if self.options.pronominal && tense == Tense::ImperativePast {
+                MissingReason::Pronominal
+            } else {
+                MissingReason::Defective
+            };
+            return Cell::missing(Some(person), reason);
+        }
+
+        let aspirate_h = self.verb.aspirate_h;
+        let forms = phrases
+            .iter()
+            .map(|phrase| {
+                (
+                    phrase.render(Subject::None, false, aspirate_h),
+                    self.is_reformed(phrase),
+                )
+            })
+            .collect();
+        // Impersonal verbs take the neutral "il" whatever the pronoun set
+        let subjects = if self.impersonal {
+            &["il"]
+        } else {
+            pronoun_set.subjects(person)
+        };
+
+        let form = self.options.form;
+        let negative = form.is_negative();
+        let display = subjects
+            .iter()
+            .flat_map(|subject| {
+                let gender = pronouns::subject_gender(subject).unwrap_or(self.options.gender);
+                self.phrases(tense, index, verb_person, gender, number, negative)
+                    .into_iter()
+                    .map(move |phrase| {
+                        let display = match tense.mood() {
+                            Mood::Imperative => phrase.render(Subject::None, negative, aspirate_h),
+                            Mood::Subjunctive => {
+                                let clause =
+                                    phrase.render(Subject::Before(subject), negative, aspirate_h);
+                                pronouns::elide("que", &clause, false)
+                            }
+                            _ if form.is_interrogative() => {
+                                phrase.question(subject, form, aspirate_h)
+                            }
+                            _ => phrase.render(Subject::Before(subject), negative, aspirate_h),
+                        };
+                        (display, self.is_reformed(&phrase))
+                    })
+            })
+            .collect();
+
+        Cell::new(Some(person), forms, display)
+    }
+
+    /// Cells of a non-finite tense (infinitives, participles, gerund)
+    fn non_finite_cells(&self, tense: Tense) -> Vec<Cell> {
+        let aspirate_h = self.verb.aspirate_h;
+        let reflexive = (self.options.pronominal && tense != Tense::PastParticiple).then_some("se");
+        let negative = self.options.form.is_negative() && tense != Tense::PastParticiple;
+        let (gender, number) = (self.options.gender, self.options.number);
+        let plain = |form: &String| Phrase {
+            reflexive: None,
+            finite: form.clone(),
+            finite_is_verb: true,
+            stressed: None,
+            participle: None,
+        };
+        let cells: Vec<Vec<Phrase>> = match tense {
+            Tense::PastParticiple => self
+                .passive_participles
+                .as_ref()
+                .unwrap_or(&self.forms[&Tense::PastParticiple])
+                .iter()
+                .map(|cell| cell.iter().map(plain).collect())
+                .collect(),
+            // "avoir mangé", "être partie": auxiliary infinitive and agreeing participle
+            Tense::InfinitivePast => {
+                let participles = self.participles(gender, number);
+                let phrases = Self::simple_cell(&self.auxiliary_forms, Tense::InfinitivePresent, 0)
+                    .iter()
+                    .flat_map(|auxiliary| {
+                        participles.iter().map(move |participle| Phrase {
+                            reflexive: None,
+                            finite: auxiliary.clone(),
+                            finite_is_verb: false,
+                            stressed: None,
+                            participle: Some(participle.clone()),
+                        })
+                    })
+                    .collect();
+                vec![self.passive(phrases, gender, number)]
+            }
+            _ => {
+                let source = match tense {
+                    Tense::Gerund => Tense::PresentParticiple,
+                    _ => tense,
+                };
+                self.forms[&source]
+                    .iter()
+                    .map(|cell| self.passive(cell.iter().map(plain).collect(), gender, number))
+                    .collect()
+            }
+        };
+        cells
+            .into_iter()
+            .map(|phrases| {
+                if phrases.is_empty() {
+                    return Cell::missing(None, MissingReason::Defective);
+                }
+                let forms = phrases
+                    .iter()
+                    .map(|phrase| {
+                        (
+                            phrase.render(Subject::None, false, aspirate_h),
+                            self.is_reformed(phrase),
+                        )
+                    })
+                    .collect();
+                let display = phrases
+                    .into_iter()
+                    .map(|phrase| {
+                        let reformed = self.is_reformed(&phrase);
+                        let phrase = Phrase {
+                            reflexive,
+                            ..phrase
+                        };
+                        let display = match tense {
+                            // Both words of the negation precede an infinitive
+                            Tense::InfinitivePresent | Tense::InfinitivePast if negative => {
+                                format!(
+                                    "ne pas {}",
+                                    phrase.render(Subject::None, false, aspirate_h)
+                                )
+                            }
+                            Tense::Gerund => {
+                                format!("en {}", phrase.render(Subject::None, negative, aspirate_h))
+                            }
+                            _ => phrase.render(Subject::None, negative, aspirate_h),
+                        };
+                        (display, reformed)
+                    })
+                    .collect();
+                Cell::new(None, forms, display)
+            })
+            .collect()
+    }
+
+    fn table(&self, tense: Tense) -> TenseTable {
+        let cells = match tense.persons() {
+            Some(persons) => persons
+                .iter()
+                .map(|&person| self.finite_cell(tense, person))
+                .collect(),
+            None => self.non_finite_cells(tense),
+        };
+        TenseTable {
+            mood: tense.mood(),
+            tense,
+            cells,
+        }
+    }
+}
+
+/// Apply the verb's template (and the auxiliary's for compound tenses) to a verb,
+/// producing every simple and compound tense. `None` when a template is missing or
+/// the passive voice is requested for a verb that has none.
+pub fn conjugate(
+    verb: &Verb,
+    templates: &HashMap<String, Template>,
+    options: &ConjugationOptions,
+) -> Option<Conjugation> {
+    let template = templates.get(&verb.template)?;
+    let (conjugated, passive_participles) = match options.voice {
+        Voice::Active => (verb.clone(), None),
+        Voice::Passive => {
+            if options.passive_restriction(&verb.verb).is_some() {
+                return None;
+            }
+            let participles = simple_forms(verb, template)?.remove(&Tense::PastParticiple);
+            (Auxiliary::Etre.verb(), participles)
+        }
+    };
+    let auxiliary = auxiliary(&conjugated.verb, options.pronominal);
+    let auxiliary_verb = auxiliary.verb();
+    let mut forms = simple_forms(&conjugated, templates.get(&conjugated.template)?)?;
+    let mut reformed = HashSet::new();
+    if options.voice == Voice::Active {
+        for (tense, cells) in forms.iter_mut() {
+            for cell in cells.iter_mut() {
+                let (spelled, tagged) = spelling::spell(&verb.verb, *tense, cell, options.spelling);
+                *cell = spelled;
+                reformed.extend(tagged);
+            }
+        }
+    }
+    let conjugator = Conjugator {
+        verb: conjugated,
+        impersonal: properties::is_impersonal(&verb.verb),
+        options,
+        auxiliary,
+        forms,
+        auxiliary_forms: simple_forms(&auxiliary_verb, templates.get(&auxiliary_verb.template)?)?,
+        passive_participles,
+        reformed,
+    };
+
+    // Only the indicative and the conditional have interrogative forms
+    let tables = |tenses: &[Tense]| -> Vec<TenseTable> {
+        tenses
+            .iter()
+            .filter(|tense| {
+                !options.form.is_interrogative()
+                    || matches!(tense.mood(), Mood::Indicative | Mood::Conditional)
+            })
+            .map(|&tense| conjugator.table(tense))
+            .collect()
+    };
+
+    Some(Conjugation {
+        verb: verb.verb.clone(),
+        template: template.name.clone(),
+        aspirate_h: verb.aspirate_h,
+        properties: properties::properties(verb, templates),
+        pronominal: options.pronominal,
+        spelling: options.spelling,
+        auxiliary,
+        voice: options.voice,
+        form: options.form,
+        tenses: tables(&Tense::ALL),
+        derived: tables(&Tense::DERIVED),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::load_all_templates;
+    use crate::verbs::load_all_verbs;
+
+    const VERBS_XML: &str = include_str!("../../data/verbs-fr.xml");
+    const CONJUGATION_XML: &str = include_str!("../../data/conjugation-fr.xml");
+
+    fn passive(verb: &str) -> Option<Conjugation> {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let verb = verbs.iter().find(|v| v.verb == verb).unwrap();
+        let options = ConjugationOptions {
+            voice: Voice::Passive,
+            ..ConjugationOptions::default()
+        };
+        conjugate(verb, &templates, &options)
+    }
+
+    #[test]
+    fn transitive_verbs_have_a_passive() {
+        assert_eq!(passive_restriction("manger", false), None);
+        assert_eq!(passive_restriction("monter", false), None);
+        let conjugation = passive("manger").unwrap();
+        let present = conjugation
+            .tenses
+            .iter()
+            .find(|table| table.tense == Tense::IndicativePresent)
+            .unwrap();
+        assert_eq!(
+            present.cells[0].display.as_ref().unwrap()[0],
+            "je suis mangé"
+        );
+    }
+
+    #[test]
+    fn other_verbs_have_no_passive() {
+        for verb in ["marcher", "nager", "tomber", "aller"] {
+            assert_eq!(
+                passive_restriction(verb, false),
+                Some("only verbs on the list of verbs taking a direct object have a passive voice"),
+                "{verb}"
+            );
+            assert!(passive(verb).is_none(), "{verb}");
+        }
+        assert!(passive_restriction("neiger", false).is_some());
+        assert!(passive_restriction("manger", true).is_some());
+    }
+
+    #[test]
+    fn derived_verbs_follow_their_base() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let (refaire, derivation) = crate::derivation::derive("refaire", &verbs).unwrap();
+        let mut options = ConjugationOptions {
+            voice: Voice::Passive,
+            ..ConjugationOptions::default()
+        };
+        assert!(options.passive_restriction("refaire").is_some());
+        assert!(conjugate(&refaire, &templates, &options).is_none());
+
+        options.derived_from = Some(derivation.base);
+        assert_eq!(options.passive_restriction("refaire"), None);
+        let conjugation = conjugate(&refaire, &templates, &options).unwrap();
+        assert_eq!(
+            conjugation.tenses[1].cells[0].display.as_ref().unwrap()[0],
+            "je suis refait"
+        );
+
+        // The base decides either way
+        options.derived_from = Some("marcher".to_string());
+        assert!(options.passive_restriction("remarcher").is_some());
+    }
+
+    #[test]
+    fn transitive_verbs_are_listed_verbs() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        for verb in TRANSITIVE_VERBS {
+            assert!(
+                verbs
+                    .binary_search_by(|v| v.verb.as_str().cmp(verb))
+                    .is_ok(),
+                "{verb}"
+            );
+        }
+    }
+}
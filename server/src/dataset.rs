@@ -0,0 +1,286 @@
+use crate::conjugation::{self, ConjugationOptions, Tense};
+use crate::export;
+use crate::properties;
+use crate::template::Template;
+use crate::verbs::Verb;
+use rusqlite::{Connection, params};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Version of the schema below, stored in the `schema_version` table; bumped on
+/// every change to the tables or to the meaning of their columns
+pub const SCHEMA_VERSION: i64 = 1;
+
+/// Tables of the dataset: templates and the endings of each of their cells,
+/// verbs with the template conjugating them, and every expanded form.
+/// Cells are numbered in template order; person, number and gender are empty
+/// when the cell has none.
+const SCHEMA: &str = "
+CREATE TABLE schema_version (
+    version INTEGER NOT NULL
+);
+CREATE TABLE templates (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    suffix TEXT NOT NULL
+);
+CREATE TABLE template_cells (
+    template_id INTEGER NOT NULL REFERENCES templates (id),
+    mood TEXT NOT NULL,
+    tense TEXT NOT NULL,
+    cell INTEGER NOT NULL,
+    person TEXT NOT NULL,
+    number TEXT NOT NULL,
+    gender TEXT NOT NULL,
+    variant INTEGER NOT NULL,
+    ending TEXT NOT NULL,
+    PRIMARY KEY (template_id, tense, cell, variant)
+);
+CREATE TABLE verbs (
+    id INTEGER PRIMARY KEY,
+    infinitive TEXT NOT NULL,
+    template_id INTEGER NOT NULL REFERENCES templates (id),
+    aspirate_h INTEGER NOT NULL,
+    conjugation_group INTEGER NOT NULL
+);
+CREATE TABLE forms (
+    verb_id INTEGER NOT NULL REFERENCES verbs (id),
+    mood TEXT NOT NULL,
+    tense TEXT NOT NULL,
+    person TEXT NOT NULL,
+    number TEXT NOT NULL,
+    gender TEXT NOT NULL,
+    variant INTEGER NOT NULL,
+    form TEXT NOT NULL
+);
+";
+
+/// Indexes, created once the tables are filled
+const INDEXES: &str = "
+CREATE UNIQUE INDEX verbs_infinitive ON verbs (infinitive);
+CREATE INDEX forms_form ON forms (form);
+CREATE INDEX forms_verb ON forms (verb_id);
+";
+
+/// Number of rows written to each table
+#[derive(Debug, Default)]
+pub struct DatasetCounts {
+    pub templates: usize,
+    pub template_cells: usize,
+    pub verbs: usize,
+    pub forms: usize,
+}
+
+/// Build the dataset at `output`, replacing any existing file. Verbs whose
+/// template is missing are left out.
+pub fn export(
+    verbs: &[Verb],
+    templates: &HashMap<String, Template>,
+    output: &Path,
+) -> Result<DatasetCounts, Box<dyn std::error::Error>> {
+    if output.exists() {
+        std::fs::remove_file(output)?;
+    }
+    let mut connection = Connection::open(output)?;
+    connection.execute_batch(SCHEMA)?;
+    let transaction = connection.transaction()?;
+    let mut counts = DatasetCounts::default();
+    transaction.execute(
+        "INSERT INTO schema_version (version) VALUES (?1)",
+        params![SCHEMA_VERSION],
+    )?;
+
+    let mut names: Vec<&String> = templates.keys().collect();
+    names.sort();
+    let mut template_ids = HashMap::new();
+    {
+        let mut insert_template =
+            transaction.prepare("INSERT INTO templates (id, name, suffix) VALUES (?1, ?2, ?3)")?;
+        let mut insert_cell = transaction.prepare(
+            "INSERT INTO template_cells
+             (template_id, mood, tense, cell, person, number, gender, variant, ending)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        for (id, name) in (1i64..).zip(names) {
+            insert_template.execute(params![id, name, conjugation::template_suffix(name)])?;
+            template_ids.insert(name.as_str(), id);
+            counts.templates += 1;
+            for tense in Tense::SIMPLE {
+                for (index, cell) in conjugation::endings(&templates[name], tense)
+                    .iter()
+                    .enumerate()
+                {
+                    let [person, number, gender] = export::cell_features(tense, index);
+                    for (variant, ending) in cell.iter().enumerate() {
+                        insert_cell.execute(params![
+                            id,
+                            tense.mood().name(),
+                            tense.name(),
+                            index as i64,
+                            person,
+                            number,
+                            gender,
+                            variant as i64,
+                            ending
+                        ])?;
+                        counts.template_cells += 1;
+                    }
+                }
+            }
+        }
+
+        let mut insert_verb = transaction.prepare(
+            "INSERT INTO verbs (id, infinitive, template_id, aspirate_h, conjugation_group)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        let mut insert_form = transaction.prepare(
+            "INSERT INTO forms (verb_id, mood, tense, person, number, gender, variant, form)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        let options = ConjugationOptions::default();
+        let mut id = 0i64;
+        for verb in verbs {
+            let Some(&template_id) = template_ids.get(verb.template.as_str()) else {
+                continue;
+            };
+            let Some(conjugation) = conjugation::conjugate(verb, templates, &options) else {
+                continue;
+            };
+            id += 1;
+            insert_verb.execute(params![
+                id,
+                verb.verb,
+                template_id,
+                verb.aspirate_h,
+                properties::group(verb, &templates[&verb.template])
+            ])?;
+            counts.verbs += 1;
+            for row in export::rows(&conjugation) {
+                insert_form.execute(params![
+                    id,
+                    row.mood,
+                    row.tense,
+                    row.person,
+                    row.number,
+                    row.gender,
+                    row.variant as i64,
+                    row.form
+                ])?;
+                counts.forms += 1;
+            }
+        }
+    }
+    transaction.execute_batch(INDEXES)?;
+    transaction.commit()?;
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data;
+
+    fn build(names: &[&str], run: &str) -> (DatasetCounts, Connection) {
+        let selected = test_data::verbs(names);
+        let templates = test_data::templates();
+        let path =
+            std::env::temp_dir().join(format!("verbiste-test-{}-{run}.sqlite", std::process::id()));
+        let counts = export(&selected, &templates, &path).unwrap();
+        let connection = Connection::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        (counts, connection)
+    }
+
+    fn count(connection: &Connection, table: &str) -> usize {
+        connection
+            .query_row(&format!("SELECT count(*) FROM {table}"), [], |row| {
+                row.get::<_, i64>(0)
+            })
+            .unwrap() as usize
+    }
+
+    #[test]
+    fn schema_has_the_documented_tables() {
+        let (_, connection) = build(&["aimer"], "schema");
+        let version: i64 = connection
+            .query_row("SELECT version FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+
+        let mut statement = connection
+            .prepare(
+                "SELECT name FROM sqlite_master
+                 WHERE type = ?1 AND name NOT LIKE 'sqlite_%' ORDER BY name",
+            )
+            .unwrap();
+        let mut names = |kind: &str| -> Vec<String> {
+            statement
+                .query_map([kind], |row| row.get(0))
+                .unwrap()
+                .map(Result::unwrap)
+                .collect()
+        };
+        let tables = [
+            "forms",
+            "schema_version",
+            "template_cells",
+            "templates",
+            "verbs",
+        ];
+        assert_eq!(names("table"), tables);
+        let indexes = ["forms_form", "forms_verb", "verbs_infinitive"];
+        assert_eq!(names("index"), indexes);
+    }
+
+    #[test]
+    fn counts_match_the_rows_written() {
+        let names = ["aimer", "asseoir", "finir"];
+        let (counts, connection) = build(&names, "counts");
+        assert_eq!(counts.templates, 148);
+        assert_eq!(counts.template_cells, 7019);
+        assert_eq!(counts.verbs, 3);
+        assert_eq!(counts.forms, 342);
+        assert_eq!(counts.templates, count(&connection, "templates"));
+        assert_eq!(counts.template_cells, count(&connection, "template_cells"));
+        assert_eq!(counts.verbs, count(&connection, "verbs"));
+        assert_eq!(counts.forms, count(&connection, "forms"));
+
+        // The forms are those of the flat export
+        let selected = test_data::verbs(&names);
+        let templates = test_data::templates();
+        let rows = export::write_table(
+            &selected,
+            &templates,
+            export::TableFormat::Csv,
+            false,
+            std::io::sink(),
+        );
+        assert_eq!(rows.unwrap(), counts.forms);
+    }
+
+    #[test]
+    fn forms_carry_their_cell_features() {
+        let (_, connection) = build(&["aimer", "asseoir"], "forms");
+        // Person, number, gender and variant of the first row of a form
+        let features = |form: &str| -> String {
+            connection
+                .query_row(
+                    "SELECT person || ',' || number || ',' || gender || ',' || variant
+                     FROM forms WHERE form = ?1",
+                    [form],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        assert_eq!(features("aimées"), ",plural,feminine,0");
+        assert_eq!(features("assoyons"), "first-plural,plural,,1");
+        let group: i64 = connection
+            .query_row(
+                "SELECT conjugation_group FROM verbs WHERE infinitive = 'aimer'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(group, 1);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::verbs::Verb;
+use serde::Serialize;
+
+/// Productive verbal prefixes, longest first so that "dés" is tried before "dé"
+const PREFIXES: &[&str] = &["contre", "entre", "pré", "dés", "sur", "re", "ré", "dé"];
+
+/// Prefixes that may be joined to the base with a hyphen ("contre-indiquer")
+const HYPHENATED_PREFIXES: &[&str] = &["contre", "entre"];
+
+/// Deepest chain of stacked prefixes that is considered
+const MAX_DEPTH: usize = 3;
+
+/// Shortest base verb accepted after removing a prefix
+const MIN_BASE_LEN: usize = 3;
+
+/// One prefixation step: `verb` is `prefix` + `base`
+#[derive(Debug, Clone, Serialize)]
+pub struct DerivationStep {
+    pub verb: String,
+    pub prefix: String,
+    pub base: String,
+}
+
+/// How an unlisted verb was derived from a listed one
+#[derive(Debug, Clone, Serialize)]
+pub struct Derivation {
+    /// Listed verb whose template is reused
+    pub base: String,
+    /// Steps from the requested verb down to the listed base
+    pub chain: Vec<DerivationStep>,
+}
+
+fn find_verb<'a>(verbs: &'a [Verb], name: &str) -> Option<&'a Verb> {
+    verbs
+        .binary_search_by(|v| v.verb.as_str().cmp(name))
+        .ok()
+        .map(|index| &verbs[index])
+}
+
+/// Possible (prefix, base) splits of a verb
+fn splits(verb: &str) -> Vec<(String, &str)> {
+    let mut result = Vec::new();
+    for prefix in PREFIXES {
+        let Some(rest) = verb.strip_prefix(prefix) else {
+            continue;
+        };
+        if HYPHENATED_PREFIXES.contains(prefix)
+            && let Some(base) = rest.strip_prefix('-')
+        {
+            result.push((format!("{prefix}-"), base));
+        }
+        result.push((prefix.to_string(), rest));
+    }
+    result
+        .into_iter()
+        .filter(|(_, base)| base.chars().count() >= MIN_BASE_LEN)
+        .collect()
+}
+
+fn derive_chain<'a>(
+    verb: &str,
+    verbs: &'a [Verb],
+    depth: usize,
+) -> Option<(&'a Verb, Vec<DerivationStep>)> {
+    if depth == 0 {
+        return None;
+    }
+
+    // Prefer a split whose base is listed over one that needs further derivation
+    let candidates = splits(verb);
+    for (prefix, base) in &candidates {
+        if let Some(known) = find_verb(verbs, base) {
+            let step = DerivationStep {
+                verb: verb.to_string(),
+                prefix: prefix.clone(),
+                base: base.to_string(),
+            };
+            return Some((known, vec![step]));
+        }
+    }
+    for (prefix, base) in &candidates {
+        if let Some((known, mut chain)) = derive_chain(base, verbs, depth - 1) {
+            chain.insert(
+                0,
+                DerivationStep {
+                    verb: verb.to_string(),
+                    prefix: prefix.clone(),
+                    base: base.to_string(),
+                },
+            );
+            return Some((known, chain));
+        }
+    }
+    None
+}
+
+/// Recognize an unlisted verb as a prefixed form of a listed one
+/// ("redécouvrir" -> re + "découvrir") and conjugate it with the base's template
+pub fn derive(verb: &str, verbs: &[Verb]) -> Option<(Verb, Derivation)> {
+    let (base, chain) = derive_chain(verb, verbs, MAX_DEPTH)?;
+    Some((
+        Verb {
+            verb: verb.to_string(),
+            template: base.template.clone(),
+            aspirate_h: false,
+        },
+        Derivation {
+            base: base.verb.clone(),
+            chain,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verbs::load_all_verbs;
+
+    const VERBS_XML: &str = include_str!("../../data/verbs-fr.xml");
+
+    fn steps(derivation: &Derivation) -> Vec<(&str, &str)> {
+        derivation
+            .chain
+            .iter()
+            .map(|step| (step.prefix.as_str(), step.base.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn prefixed_verb_reuses_the_base_template() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let protéger = find_verb(&verbs, "protéger").unwrap();
+
+        let (verb, derivation) = derive("surprotéger", &verbs).unwrap();
+        assert_eq!(verb.verb, "surprotéger");
+        assert_eq!(verb.template, protéger.template);
+        assert_eq!(derivation.base, "protéger");
+        assert_eq!(steps(&derivation), [("sur", "protéger")]);
+    }
+
+    #[test]
+    fn stacked_and_hyphenated_prefixes() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+
+        let (_, derivation) = derive("resurprotéger", &verbs).unwrap();
+        assert_eq!(derivation.base, "protéger");
+        assert_eq!(
+            steps(&derivation),
+            [("re", "surprotéger"), ("sur", "protéger")]
+        );
+
+        let (_, derivation) = derive("contre-chanter", &verbs).unwrap();
+        assert_eq!(steps(&derivation), [("contre-", "chanter")]);
+    }
+
+    #[test]
+    fn no_stripping_when_the_remainder_is_not_a_verb() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        assert!(derive("reblurpiner", &verbs).is_none());
+        assert!(derive("surblurper", &verbs).is_none());
+        assert!(derive("dézorgler", &verbs).is_none());
+        // Bases shorter than three letters are never tried
+        assert!(splits("reer").is_empty());
+    }
+}
@@ -0,0 +1,316 @@
+use crate::conjugation::{self, Conjugation, ConjugationOptions};
+use crate::export;
+use crate::render::escape_html;
+use crate::spelling::Spelling;
+use crate::template::Template;
+use crate::verbs::Verb;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+use std::path::Path;
+
+/// Title of the dictionaries
+const TITLE: &str = "Verbiste – conjugaison des verbes français";
+
+/// Entries per content file of the Kindle dictionary, which handles many
+/// small files better than one large one
+const ENTRIES_PER_FILE: usize = 250;
+
+/// Namespace of the Kindle index markup
+const KINDLE_NAMESPACE: &str =
+    "https://kindlegen.s3.amazonaws.com/AmazonKindlePublishingGuidelines.pdf";
+
+/// Output of a dictionary export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DictionaryFormat {
+    /// StarDict files (.ifo, .idx, .dict and .syn)
+    #[default]
+    Stardict,
+    /// EPUB with Kindle index markup, to be converted with Kindle Previewer
+    Kindle,
+}
+
+/// A verb: its conjugation table and every single-word form looking it up
+struct Entry {
+    headword: String,
+    /// HTML conjugation table
+    definition: String,
+    /// Forms other than the headword, sorted and without duplicates
+    inflections: Vec<String>,
+}
+
+/// Conjugation tables as HTML, one paragraph per tense
+fn definition(conjugation: &Conjugation) -> String {
+    let mut html = String::new();
+    for table in conjugation.tenses.iter().chain(&conjugation.derived) {
+        let lines: Vec<String> = table
+            .cells
+            .iter()
+            .map(|cell| match &cell.display {
+                Some(display) => escape_html(&display.join(" / ")),
+                None => "—".to_string(),
+            })
+            .collect();
+        html.push_str(&format!(
+            "<p><b>{}</b><br/>{}</p>",
+            table.tense.label(),
+            lines.join("<br/>")
+        ));
+    }
+    html
+}
+
+/// Entries of every verb, in verb order. Both spellings are listed so that
+/// 1990 spellings also find their lemma.
+fn entries(verbs: &[Verb], templates: &HashMap<String, Template>) -> Vec<Entry> {
+    let options = ConjugationOptions {
+        spelling: Spelling::Both,
+        ..ConjugationOptions::default()
+    };
+    verbs
+        .iter()
+        .filter_map(|verb| {
+            let conjugation = conjugation::conjugate(verb, templates, &options)?;
+            let inflections: BTreeSet<String> = export::rows(&conjugation)
+                .map(|row| row.form)
+                .filter(|form| {
+                    !form.is_empty() && !form.contains(char::is_whitespace) && *form != verb.verb
+                })
+                .map(str::to_string)
+                .collect();
+            Some(Entry {
+                headword: verb.verb.clone(),
+                definition: definition(&conjugation),
+                inflections: inflections.into_iter().collect(),
+            })
+        })
+        .collect()
+}
+
+/// Order of StarDict indexes: ASCII case-insensitive, then byte order
+fn stardict_order(a: &str, b: &str) -> Ordering {
+    let folded = |text: &str| {
+        text.bytes()
+            .map(|byte| byte.to_ascii_lowercase())
+            .collect::<Vec<_>>()
+    };
+    folded(a).cmp(&folded(b)).then_with(|| a.cmp(b))
+}
+
+/// Files of a StarDict dictionary
+#[derive(Debug, Clone)]
+pub struct StarDict {
+    pub ifo: String,
+    pub idx: Vec<u8>,
+    pub dict: Vec<u8>,
+    /// Inflected forms, each pointing to the index entry of its lemma
+    pub syn: Vec<u8>,
+}
+
+/// Build a StarDict dictionary with HTML definitions
+pub fn stardict(verbs: &[Verb], templates: &HashMap<String, Template>) -> StarDict {
+    let mut entries = entries(verbs, templates);
+    entries.sort_by(|a, b| stardict_order(&a.headword, &b.headword));
+
+    let mut idx = Vec::new();
+    let mut dict = Vec::new();
+    let mut synonyms = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        idx.extend_from_slice(entry.headword.as_bytes());
+        idx.push(0);
+        idx.extend_from_slice(&(dict.len() as u32).to_be_bytes());
+        idx.extend_from_slice(&(entry.definition.len() as u32).to_be_bytes());
+        dict.extend_from_slice(entry.definition.as_bytes());
+        synonyms.extend(
+            entry
+                .inflections
+                .iter()
+                .map(|form| (form.as_str(), index as u32)),
+        );
+    }
+    synonyms.sort_by(|a, b| stardict_order(a.0, b.0).then(a.1.cmp(&b.1)));
+    let mut syn = Vec::new();
+    for (form, index) in &synonyms {
+        syn.extend_from_slice(form.as_bytes());
+        syn.push(0);
+        syn.extend_from_slice(&index.to_be_bytes());
+    }
+
+    let ifo = format!(
+        "StarDict's dict ifo file\nversion=3.0.0\nbookname={TITLE}\nwordcount={}\n\
+         synwordcount={}\nidxfilesize={}\nsametypesequence=h\n",
+        entries.len(),
+        synonyms.len(),
+        idx.len()
+    );
+    StarDict {
+        ifo,
+        idx,
+        dict,
+        syn,
+    }
+}
+
+/// Content file of a Kindle dictionary: each entry is indexed under its
+/// headword, with an inflection group mapping every form to it
+fn kindle_content(entries: &[Entry]) -> String {
+    let mut html = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:idx=\"{KINDLE_NAMESPACE}\" \
+         xmlns:mbp=\"{KINDLE_NAMESPACE}\">\n\
+         <head><meta http-equiv=\"Content-Type\" content=\"text/html; charset=utf-8\"/></head>\n\
+         <body>\n<mbp:frameset>\n"
+    );
+    for entry in entries {
+        let headword = escape_html(&entry.headword);
+        html.push_str(&format!(
+            "<idx:entry name=\"french\" scriptable=\"yes\" spell=\"yes\">\n\
+             <idx:orth value=\"{headword}\"><b>{headword}</b>\n<idx:infl>\n"
+        ));
+        for form in &entry.inflections {
+            html.push_str(&format!("<idx:iform value=\"{}\"/>\n", escape_html(form)));
+        }
+        html.push_str(&format!(
+            "</idx:infl>\n</idx:orth>\n{}\n</idx:entry>\n<hr/>\n",
+            entry.definition
+        ));
+    }
+    html.push_str("</mbp:frameset>\n</body>\n</html>\n");
+    html
+}
+
+/// Write a Kindle dictionary as an EPUB: an OPF package declaring a French
+/// dictionary, an NCX table of contents and the entries split into content
+/// files. Returns the number of entries.
+pub fn kindle(
+    verbs: &[Verb],
+    templates: &HashMap<String, Template>,
+    output: &Path,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let entries = entries(verbs, templates);
+    let files: Vec<&[Entry]> = entries.chunks(ENTRIES_PER_FILE).collect();
+    let title = escape_html(TITLE);
+
+    let manifest: String = (0..files.len())
+        .map(|index| {
+            format!(
+                "<item id=\"entries{index}\" href=\"entries{index}.xhtml\" \
+                 media-type=\"application/xhtml+xml\"/>\n"
+            )
+        })
+        .collect();
+    let spine: String = (0..files.len())
+        .map(|index| format!("<itemref idref=\"entries{index}\"/>\n"))
+        .collect();
+    let opf = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <package version=\"2.0\" xmlns=\"http://www.idpf.org/2007/opf\" unique-identifier=\"id\">\n\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n\
+         <dc:title>{title}</dc:title>\n<dc:language>fr</dc:language>\n\
+         <dc:identifier id=\"id\">verbiste-conjugations</dc:identifier>\n\
+         <x-metadata>\n<DictionaryInLanguage>fr</DictionaryInLanguage>\n\
+         <DictionaryOutLanguage>fr</DictionaryOutLanguage>\n\
+         <DefaultLookupIndex>french</DefaultLookupIndex>\n</x-metadata>\n\
+         </metadata>\n<manifest>\n\
+         <item id=\"ncx\" href=\"toc.ncx\" media-type=\"application/x-dtbncx+xml\"/>\n\
+         {manifest}</manifest>\n<spine toc=\"ncx\">\n{spine}</spine>\n</package>\n"
+    );
+    let ncx = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <ncx xmlns=\"http://www.daisy.org/z3986/2005/ncx/\" version=\"2005-1\">\n\
+         <head><meta name=\"dtb:uid\" content=\"verbiste-conjugations\"/></head>\n\
+         <docTitle><text>{title}</text></docTitle>\n<navMap>\n\
+         <navPoint id=\"entries0\" playOrder=\"1\"><navLabel><text>{title}</text></navLabel>\
+         <content src=\"entries0.xhtml\"/></navPoint>\n</navMap>\n</ncx>\n"
+    );
+    let container = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n\
+         <rootfiles><rootfile full-path=\"OEBPS/content.opf\" \
+         media-type=\"application/oebps-package+xml\"/></rootfiles>\n</container>\n";
+
+    let mut archive = zip::ZipWriter::new(std::fs::File::create(output)?);
+    // The mimetype comes first and uncompressed so that readers can sniff it
+    archive.start_file(
+        "mimetype",
+        zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored),
+    )?;
+    archive.write_all(b"application/epub+zip")?;
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    archive.start_file("META-INF/container.xml", options)?;
+    archive.write_all(container.as_bytes())?;
+    archive.start_file("OEBPS/content.opf", options)?;
+    archive.write_all(opf.as_bytes())?;
+    archive.start_file("OEBPS/toc.ncx", options)?;
+    archive.write_all(ncx.as_bytes())?;
+    for (index, file) in files.iter().enumerate() {
+        archive.start_file(format!("OEBPS/entries{index}.xhtml"), options)?;
+        archive.write_all(kindle_content(file).as_bytes())?;
+    }
+    archive.finish()?;
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data;
+
+    /// Words of an index file and the `trailer` bytes following each
+    fn index_entries(index: &[u8], trailer: usize) -> Vec<(String, &[u8])> {
+        let mut entries = Vec::new();
+        let mut rest = index;
+        while !rest.is_empty() {
+            let end = rest.iter().position(|&byte| byte == 0).unwrap();
+            let word = String::from_utf8(rest[..end].to_vec()).unwrap();
+            entries.push((word, &rest[end + 1..end + 1 + trailer]));
+            rest = &rest[end + 1 + trailer..];
+        }
+        entries
+    }
+
+    #[test]
+    fn stardict_order_folds_ascii_case_only() {
+        let mut words = vec!["étudier", "Zoner", "abattre", "Abattre", "zézayer"];
+        words.sort_by(|a, b| stardict_order(a, b));
+        assert_eq!(words, ["Abattre", "abattre", "Zoner", "zézayer", "étudier"]);
+    }
+
+    #[test]
+    fn inflected_forms_look_up_their_lemma() {
+        let selected = test_data::verbs(&["recevoir", "aimer", "céder", "être"]);
+        let templates = test_data::templates();
+        let dictionary = stardict(&selected, &templates);
+
+        let idx = index_entries(&dictionary.idx, 8);
+        let headwords: Vec<&str> = idx.iter().map(|(word, _)| word.as_str()).collect();
+        assert_eq!(headwords, ["aimer", "céder", "recevoir", "être"]);
+        // Definitions follow one another in index order
+        let mut offset = 0;
+        for (_, trailer) in &idx {
+            assert_eq!(u32::from_be_bytes(trailer[..4].try_into().unwrap()), offset);
+            offset += u32::from_be_bytes(trailer[4..].try_into().unwrap());
+        }
+        assert_eq!(offset as usize, dictionary.dict.len());
+
+        let syn = index_entries(&dictionary.syn, 4);
+        assert!(
+            syn.windows(2)
+                .all(|pair| { stardict_order(&pair[0].0, &pair[1].0) != Ordering::Greater })
+        );
+        let lemma = |form: &str| {
+            let (_, trailer) = syn.iter().find(|(word, _)| word == form).unwrap();
+            headwords[u32::from_be_bytes((*trailer).try_into().unwrap()) as usize]
+        };
+        assert_eq!(lemma("reçûmes"), "recevoir");
+        assert_eq!(lemma("fûmes"), "être");
+        // Both spellings of the future of "céder"
+        assert_eq!(lemma("céderai"), "céder");
+        assert_eq!(lemma("cèderai"), "céder");
+        assert!(!syn.iter().any(|(word, _)| word == "aimer"));
+
+        assert!(dictionary.ifo.contains("\nwordcount=4\n"));
+        let synonyms = format!("\nsynwordcount={}\n", syn.len());
+        assert!(dictionary.ifo.contains(&synonyms));
+    }
+}
@@ -0,0 +1,612 @@
+use crate::conjugation::{
+    self, Auxiliary, Conjugation, ConjugationOptions, Gender, Mood, Person, Tense,
+};
+use crate::pronouns::PronounSet;
+use crate::properties;
+use crate::spelling::Spelling;
+use crate::template::Template;
+use crate::verbs::Verb;
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Attempts at picking a cell before giving up, defective verbs lacking some
+const MAX_ATTEMPTS: usize = 100;
+
+/// Filters of a drill question, read from the query string
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DrillQuery {
+    /// Only verbs of this template ("aim:er")
+    pub template: Option<String>,
+    /// Only verbs of this conjugation group (1, 2 or 3)
+    pub group: Option<u8>,
+    /// Comma-separated tense names; every finite tense when omitted
+    pub tenses: Option<String>,
+    /// Seed making the pick reproducible
+    pub seed: Option<u64>,
+}
+
+/// A cell to conjugate
+#[derive(Debug, Clone, Serialize)]
+pub struct Drill {
+    pub verb: String,
+    pub template: String,
+    pub group: u8,
+    pub mood: Mood,
+    pub tense: Tense,
+    pub person: Person,
+    /// Gender of the subject, deciding the agreement of participles after "être"
+    pub gender: Gender,
+    /// Subject pronoun to show before the blank; `None` for the imperative
+    pub subject: Option<&'static str>,
+}
+
+/// An answer to a drill question, posted as JSON
+#[derive(Debug, Clone, Deserialize)]
+pub struct DrillAnswer {
+    pub verb: String,
+    pub tense: Tense,
+    pub person: Person,
+    #[serde(default)]
+    pub gender: Gender,
+    pub answer: String,
+}
+
+/// Kind of mistake in a wrong answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mistake {
+    /// Right letters, wrong or missing accents ("cede" for "cède")
+    AccentOnly,
+    /// Right stem, wrong ending ("finirons" for "finissons")
+    WrongEnding,
+    /// Right ending, wrong stem ("apelle" for "appelle")
+    WrongStem,
+    /// "avoir" instead of "être" or the reverse ("j'ai allé")
+    WrongAuxiliary,
+    /// Past participle in the wrong gender or number ("elle est allé")
+    WrongAgreement,
+    /// Not close to any valid form
+    Other,
+}
+
+/// Grade of an answer
+#[derive(Debug, Clone, Serialize)]
+pub struct DrillResult {
+    pub correct: bool,
+    /// The answer as compared, without subject pronoun
+    pub answer: String,
+    /// Every accepted form, as displayed with its subject pronoun
+    pub expected: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mistake: Option<Mistake>,
+}
+
+/// Parse a comma-separated list of tense names, keeping only tenses with persons
+pub fn parse_tenses(names: Option<&str>) -> Result<Vec<Tense>, String> {
+    let Some(names) = names else {
+        return Ok(Tense::ALL
+            .into_iter()
+            .filter(|tense| tense.persons().is_some())
+            .collect());
+    };
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let tense = Tense::ALL
+                .into_iter()
+                .find(|tense| tense.name() == name)
+                .ok_or_else(|| format!("unknown tense {name}"))?;
+            match tense.persons() {
+                Some(_) => Ok(tense),
+                None => Err(format!("{name} has no persons to drill")),
+            }
+        })
+        .collect()
+}
+
+/// Subject pronoun shown for a person, the third persons following the gender
+fn subject(person: Person, gender: Gender) -> &'static str {
+    match (person, gender) {
+        (Person::FirstSingular, _) => "je",
+        (Person::SecondSingular, _) => "tu",
+        (Person::ThirdSingular, Gender::Masculine) => "il",
+        (Person::ThirdSingular, Gender::Feminine) => "elle",
+        (Person::FirstPlural, _) => "nous",
+        (Person::SecondPlural, _) => "vous",
+        (Person::ThirdPlural, Gender::Masculine) => "ils",
+        (Person::ThirdPlural, Gender::Feminine) => "elles",
+    }
+}
+
+/// Conjugation options rendering the cells of a drill: both spellings are valid,
+/// and third-person subjects carry the gender
+fn options(gender: Gender) -> ConjugationOptions {
+    ConjugationOptions {
+        pronouns: match gender {
+            Gender::Masculine => PronounSet::Masculine,
+            Gender::Feminine => PronounSet::Feminine,
+        },
+        gender,
+        spelling: Spelling::Both,
+        ..ConjugationOptions::default()
+    }
+}
+
+/// Bare forms and display strings of one cell of a conjugation
+fn cell_of(
+    conjugation: &Conjugation,
+    tense: Tense,
+    person: Person,
+) -> Option<(Vec<String>, Vec<String>)> {
+    let cell = conjugation
+        .tenses
+        .iter()
+        .find(|table| table.tense == tense)?
+        .cells
+        .iter()
+        .find(|cell| cell.person == Some(person))?;
+    Some((cell.forms.clone()?, cell.display.clone()?))
+}
+
+/// Bare forms and display strings of one cell; `None` when the cell does not exist
+pub fn cell(
+    verb: &Verb,
+    templates: &HashMap<String, Template>,
+    tense: Tense,
+    person: Person,
+    gender: Gender,
+) -> Option<(Vec<String>, Vec<String>)> {
+    let conjugation = conjugation::conjugate(verb, templates, &options(gender))?;
+    cell_of(&conjugation, tense, person)
+}
+
+/// Conjugations of the verbs drawn while picking cells, computed once per verb
+/// and subject gender
+pub struct Conjugations<'a> {
+    templates: &'a HashMap<String, Template>,
+    conjugations: HashMap<(String, Gender), Option<Conjugation>>,
+}
+
+impl<'a> Conjugations<'a> {
+    pub fn new(templates: &'a HashMap<String, Template>) -> Self {
+        Conjugations {
+            templates,
+            conjugations: HashMap::new(),
+        }
+    }
+
+    /// Bare forms and display strings of one cell; `None` when the cell does
+    /// not exist
+    pub fn cell(
+        &mut self,
+        verb: &Verb,
+        tense: Tense,
+        person: Person,
+        gender: Gender,
+    ) -> Option<(Vec<String>, Vec<String>)> {
+        let templates = self.templates;
+        let conjugation = self
+            .conjugations
+            .entry((verb.verb.clone(), gender))
+            .or_insert_with(|| conjugation::conjugate(verb, templates, &options(gender)));
+        cell_of(conjugation.as_ref()?, tense, person)
+    }
+}
+
+/// Drill a random person of one tense of a verb; `None` when the drawn cell does
+/// not exist
+pub fn drill(
+    verb: &Verb,
+    conjugations: &mut Conjugations,
+    tense: Tense,
+    rng: &mut impl Rng,
+) -> Option<Drill> {
+    let template = conjugations.templates.get(&verb.template)?;
+    // Impersonal verbs only have "il" forms
+    let impersonal = properties::is_impersonal(&verb.verb);
+    let person = if impersonal {
+        Person::ThirdSingular
+    } else {
+        *tense.persons()?.choose(rng)?
+    };
+    let gender = if !impersonal && rng.random_bool(0.5) {
+        Gender::Feminine
+    } else {
+        Gender::Masculine
+    };
+    conjugations.cell(verb, tense, person, gender)?;
+    Some(Drill {
+        verb: verb.verb.clone(),
+        template: verb.template.clone(),
+        group: properties::group(verb, template),
+        mood: tense.mood(),
+        tense,
+        person,
+        gender,
+        subject: (tense.mood() != Mood::Imperative).then(|| subject(person, gender)),
+    })
+}
+
+/// Dictionary entries of a comma-separated list of infinitives
+pub fn named<'a>(verbs: &'a [Verb], names: &str) -> Result<Vec<&'a Verb>, String> {
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            verbs
+                .binary_search_by(|v| v.verb.as_str().cmp(name))
+                .map(|index| &verbs[index])
+                .map_err(|_| format!("unknown verb {name}"))
+        })
+        .collect()
+}
+
+/// Whether a verb has the given template and conjugation group, when set
+pub fn matches(
+    verb: &Verb,
+    templates: &HashMap<String, Template>,
+    template: Option<&str>,
+    group: Option<u8>,
+) -> bool {
+    template.is_none_or(|template| template == verb.template)
+        && group.is_none_or(|group| {
+            templates
+                .get(&verb.template)
+                .is_some_and(|template| group == properties::group(verb, template))
+        })
+}
+
+/// Verbs of the given template and conjugation group, when set
+pub fn candidates<'a>(
+    verbs: &'a [Verb],
+    templates: &HashMap<String, Template>,
+    template: Option<&str>,
+    group: Option<u8>,
+) -> Vec<&'a Verb> {
+    verbs
+        .iter()
+        .filter(|verb| matches(verb, templates, template, group))
+        .collect()
+}
+
+/// Pick a random verb, tense and person among those matching the query
+pub fn pick(
+    verbs: &[Verb],
+    templates: &HashMap<String, Template>,
+    query: &DrillQuery,
+) -> Result<Drill, String> {
+    pick_by(verbs, templates, query, |_, _| true)
+}
+
+/// Pick a random verb, tense and person among those matching the query and
+/// accepted by a custom filter on the verb and tense
+pub fn pick_by(
+    verbs: &[Verb],
+    templates: &HashMap<String, Template>,
+    query: &DrillQuery,
+    accept: impl Fn(&Verb, Tense) -> bool,
+) -> Result<Drill, String> {
+    let tenses = parse_tenses(query.tenses.as_deref())?;
+    if tenses.is_empty() {
+        return Err("no tense to drill".into());
+    }
+    let candidates = candidates(verbs, templates, query.template.as_deref(), query.group);
+    if candidates.is_empty() {
+        return Err("no verb matches the filters".into());
+    }
+
+    let mut rng = match query.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+    let mut conjugations = Conjugations::new(templates);
+    for _ in 0..MAX_ATTEMPTS {
+        let verb = *candidates.choose(&mut rng).unwrap();
+        let tense = *tenses.choose(&mut rng).unwrap();
+        if !accept(verb, tense) {
+            continue;
+        }
+        if let Some(drill) = drill(verb, &mut conjugations, tense, &mut rng) {
+            return Ok(drill);
+        }
+    }
+    Err("no existing form matches the filters".into())
+}
+
+/// Lowercase an answer, normalize apostrophes and spaces and drop a leading
+/// subject pronoun ("que j'aie" -> "aie")
+fn normalize(answer: &str) -> String {
+    let answer = answer
+        .to_lowercase()
+        .replace('\u{2019}', "'")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut rest = answer.as_str();
+    for prefix in ["que ", "qu'"] {
+        if let Some(stripped) = rest.strip_prefix(prefix) {
+            rest = stripped;
+            break;
+        }
+    }
+    if let Some(stripped) = rest.strip_prefix("j'") {
+        return stripped.to_string();
+    }
+    for subject in [
+        "je", "tu", "il", "elle", "on", "iel", "nous", "vous", "ils", "elles", "iels",
+    ] {
+        if let Some(stripped) = rest
+            .strip_prefix(subject)
+            .and_then(|rest| rest.strip_prefix(' '))
+        {
+            return stripped.to_string();
+        }
+    }
+    rest.to_string()
+}
+
+/// Length in characters of the common prefix of two strings
+fn common_prefix(a: &str, b: &str) -> usize {
+    a.chars().zip(b.chars()).take_while(|(a, b)| a == b).count()
+}
+
+/// Classify a wrong word against the expected one of the same verb: stem kept
+/// and ending changed, or ending kept and stem changed
+fn classify_word(answer: &str, expected: &str, stem: &str) -> Mistake {
+    let stem_length = stem.chars().count();
+    let ending: String = expected.chars().skip(stem_length).collect();
+    if stem_length > 0 && common_prefix(answer, expected) >= stem_length {
+        Mistake::WrongEnding
+    } else if !ending.is_empty() && answer.ends_with(&ending) {
+        Mistake::WrongStem
+    } else {
+        Mistake::Other
+    }
+}
+
+/// Forms of a verb and its auxiliaries used to tell mistakes apart
+struct Reference<'a> {
+    stem: &'a str,
+    /// Every past participle, whatever its agreement
+    participles: Vec<String>,
+    /// Auxiliary expected in compound tenses, and the forms of both auxiliaries
+    /// in the auxiliary tense for the drilled person
+    auxiliary: Auxiliary,
+    auxiliary_forms: HashMap<Auxiliary, Vec<String>>,
+}
+
+impl Reference<'_> {
+    fn classify(&self, tense: Tense, answer: &str, expected: &str) -> Mistake {
+        if deunicode::deunicode(answer) == deunicode::deunicode(expected) {
+            return Mistake::AccentOnly;
+        }
+        if tense.auxiliary_tense().is_none() {
+            return classify_word(answer, expected, self.stem);
+        }
+        let (
+            Some((answer_auxiliary, answer_participle)),
+            Some((expected_auxiliary, expected_participle)),
+        ) = (answer.split_once(' '), expected.split_once(' '))
+        else {
+            return Mistake::Other;
+        };
+        if answer_auxiliary != expected_auxiliary {
+            let other = match self.auxiliary {
+                Auxiliary::Avoir => Auxiliary::Etre,
+                Auxiliary::Etre => Auxiliary::Avoir,
+            };
+            let is_other = self.auxiliary_forms[&other]
+                .iter()
+                .any(|form| form == answer_auxiliary);
+            return if is_other {
+                Mistake::WrongAuxiliary
+            } else {
+                classify_word(answer_auxiliary, expected_auxiliary, "")
+            };
+        }
+        if self
+            .participles
+            .iter()
+            .any(|participle| participle == answer_participle)
+        {
+            Mistake::WrongAgreement
+        } else if deunicode::deunicode(answer_participle)
+            == deunicode::deunicode(expected_participle)
+        {
+            Mistake::AccentOnly
+        } else {
+            classify_word(answer_participle, expected_participle, self.stem)
+        }
+    }
+}
+
+/// Most specific mistake over the accepted forms
+fn mistake(reference: &Reference, tense: Tense, answer: &str, forms: &[String]) -> Mistake {
+    const PRIORITY: [Mistake; 6] = [
+        Mistake::AccentOnly,
+        Mistake::WrongAgreement,
+        Mistake::WrongAuxiliary,
+        Mistake::WrongEnding,
+        Mistake::WrongStem,
+        Mistake::Other,
+    ];
+    forms
+        .iter()
+        .map(|form| reference.classify(tense, answer, form))
+        .min_by_key(|mistake| PRIORITY.iter().position(|m| m == mistake))
+        .unwrap_or(Mistake::Other)
+}
+
+/// Grade an answer against every variant of the cell, classifying the mistake
+/// when none matches
+pub fn check(
+    verb: &Verb,
+    templates: &HashMap<String, Template>,
+    answer: &DrillAnswer,
+) -> Result<DrillResult, String> {
+    let Some(persons) = answer.tense.persons() else {
+        return Err(format!("{} has no persons to drill", answer.tense.name()));
+    };
+    let (forms, display) = cell(verb, templates, answer.tense, answer.person, answer.gender)
+        .ok_or_else(|| {
+            format!(
+                "{} has no {} form for this person",
+                verb.verb,
+                answer.tense.name()
+            )
+        })?;
+    let given = normalize(&answer.answer);
+    if forms.contains(&given) {
+        return Ok(DrillResult {
+            correct: true,
+            answer: given,
+            expected: display,
+            mistake: None,
+        });
+    }
+
+    let template = templates
+        .get(&verb.template)
+        .ok_or_else(|| format!("{}: unknown template {}", verb.verb, verb.template))?;
+    let simple_forms = conjugation::simple_forms(verb, template)
+        .ok_or_else(|| format!("{}: unknown template {}", verb.verb, verb.template))?;
+    let index = persons
+        .iter()
+        .position(|&person| person == answer.person)
+        .unwrap_or_default();
+    let auxiliary_forms = [Auxiliary::Avoir, Auxiliary::Etre]
+        .into_iter()
+        .map(|auxiliary| {
+            let auxiliary_verb = auxiliary.verb();
+            let forms = answer
+                .tense
+                .auxiliary_tense()
+                .zip(templates.get(&auxiliary_verb.template))
+                .and_then(|(tense, template)| {
+                    conjugation::simple_forms(&auxiliary_verb, template)?
+                        .remove(&tense)?
+                        .into_iter()
+                        .nth(index)
+                })
+                .unwrap_or_default();
+            (auxiliary, forms)
+        })
+        .collect();
+    let reference = Reference {
+        stem: conjugation::stem(&verb.verb, &verb.template).unwrap_or_default(),
+        participles: simple_forms[&Tense::PastParticiple].concat(),
+        auxiliary: conjugation::auxiliary(&verb.verb, false),
+        auxiliary_forms,
+    };
+
+    Ok(DrillResult {
+        correct: false,
+        mistake: Some(mistake(&reference, answer.tense, &given, &forms)),
+        answer: given,
+        expected: display,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::load_all_templates;
+    use crate::verbs::load_all_verbs;
+
+    const VERBS_XML: &str = include_str!("../../data/verbs-fr.xml");
+    const CONJUGATION_XML: &str = include_str!("../../data/conjugation-fr.xml");
+
+    #[test]
+    fn normalized_answers() {
+        let cases = [
+            ("Je Cède", "cède"),
+            ("  nous   finissons ", "finissons"),
+            ("que j\u{2019}aie", "aie"),
+            ("qu'il finisse", "finisse"),
+            ("elle est allée", "est allée"),
+            ("j'ai été", "ai été"),
+            ("finis", "finis"),
+            // Only a whole pronoun is dropped
+            ("ilote", "ilote"),
+        ];
+        for (answer, expected) in cases {
+            assert_eq!(normalize(answer), expected, "{answer}");
+        }
+    }
+
+    #[test]
+    fn graded_answers() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let grade = |(verb, tense, person, gender), answer: &str| {
+            let verb = verbs.iter().find(|v| v.verb == verb).unwrap();
+            let answer = DrillAnswer {
+                verb: verb.verb.clone(),
+                tense,
+                person,
+                gender,
+                answer: answer.to_string(),
+            };
+            let result = check(verb, &templates, &answer).unwrap();
+            (result.correct, result.mistake)
+        };
+        use Gender::{Feminine, Masculine};
+        use Person::{FirstPlural, FirstSingular, ThirdSingular};
+        use Tense::{IndicativeFuture, IndicativePerfect, IndicativePresent};
+        let je_cède = ("céder", IndicativePresent, FirstSingular, Masculine);
+        let je_céderai = ("céder", IndicativeFuture, FirstSingular, Masculine);
+        let il_paraît = ("paraître", IndicativePresent, ThirdSingular, Masculine);
+        let nous_finissons = ("finir", IndicativePresent, FirstPlural, Masculine);
+        let j_appelle = ("appeler", IndicativePresent, FirstSingular, Masculine);
+        let je_suis_allé = ("aller", IndicativePerfect, FirstSingular, Masculine);
+        let elle_est_allée = ("aller", IndicativePerfect, ThirdSingular, Feminine);
+        let cases = [
+            // Case and subject pronouns are ignored
+            (je_cède, "JE CÈDE", None),
+            // Both spellings of a cell are accepted
+            (je_céderai, "céderai", None),
+            (je_céderai, "cèderai", None),
+            (il_paraît, "il parait", None),
+            (je_cède, "cede", Some(Mistake::AccentOnly)),
+            (elle_est_allée, "elle est allee", Some(Mistake::AccentOnly)),
+            (nous_finissons, "finirons", Some(Mistake::WrongEnding)),
+            (j_appelle, "apelle", Some(Mistake::WrongStem)),
+            (je_suis_allé, "j'ai allé", Some(Mistake::WrongAuxiliary)),
+            (
+                elle_est_allée,
+                "elle est allé",
+                Some(Mistake::WrongAgreement),
+            ),
+            (j_appelle, "xyz", Some(Mistake::Other)),
+        ];
+        for (cell, answer, mistake) in cases {
+            let expected = (mistake.is_none(), mistake);
+            assert_eq!(grade(cell, answer), expected, "{answer}");
+        }
+    }
+
+    #[test]
+    fn seeded_picks_are_reproducible() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let query = DrillQuery {
+            tenses: Some("indicative-present".to_string()),
+            seed: Some(7),
+            ..DrillQuery::default()
+        };
+        let first = pick(&verbs, &templates, &query).unwrap();
+        let second = pick(&verbs, &templates, &query).unwrap();
+        assert_eq!(first.tense, Tense::IndicativePresent);
+        assert_eq!(
+            (first.verb, first.person, first.gender),
+            (second.verb, second.person, second.gender)
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use crate::conjugation::{self, Conjugation, ConjugationOptions, Tense};
+use crate::template::Template;
+use crate::verbs::Verb;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Verbs per page of `/api/export` when no limit is given, and the largest page
+pub const DEFAULT_PAGE_SIZE: usize = 500;
+pub const MAX_PAGE_SIZE: usize = 2000;
+
+/// Columns of the inflected lexicon
+pub const HEADER: [&str; 9] = [
+    "verb", "template", "mood", "tense", "person", "number", "gender", "form", "variant",
+];
+
+/// Delimited text format of a table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum TableFormat {
+    #[default]
+    Csv,
+    Tsv,
+}
+
+impl TableFormat {
+    fn delimiter(self) -> u8 {
+        match self {
+            TableFormat::Csv => b',',
+            TableFormat::Tsv => b'\t',
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            TableFormat::Csv => "text/csv; charset=utf-8",
+            TableFormat::Tsv => "text/tab-separated-values; charset=utf-8",
+        }
+    }
+}
+
+/// Page of the inflected lexicon, read from the query string
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportQuery {
+    pub format: TableFormat,
+    /// Index of the first verb of the page
+    pub offset: usize,
+    /// Number of verbs in the page, `DEFAULT_PAGE_SIZE` when absent
+    pub limit: Option<usize>,
+}
+
+/// Person, number and gender columns of a cell of a tense: finite cells have a
+/// person, past participle cells a gender and number (masc. sing., masc. pl.,
+/// fem. sing., fem. pl.)
+pub fn cell_features(tense: Tense, index: usize) -> [&'static str; 3] {
+    let number = |number: conjugation::Number| match number {
+        conjugation::Number::Singular => "singular",
+        conjugation::Number::Plural => "plural",
+    };
+    match (
+        tense.persons().and_then(|persons| persons.get(index)),
+        tense,
+    ) {
+        (Some(person), _) => [person.name(), number(person.number()), ""],
+        (None, Tense::PastParticiple) => [
+            "",
+            if index.is_multiple_of(2) {
+                "singular"
+            } else {
+                "plural"
+            },
+            if index < 2 { "masculine" } else { "feminine" },
+        ],
+        (None, _) => ["", "", ""],
+    }
+}
+
+/// A form of a conjugation with the features of its cell
+pub struct Row<'a> {
+    pub mood: &'static str,
+    pub tense: &'static str,
+    pub person: &'static str,
+    pub number: &'static str,
+    pub gender: &'static str,
+    pub form: &'a str,
+    /// Index of the form among the variants of its cell
+    pub variant: usize,
+}
+
+/// Every form of a conjugation, simple and compound tenses then derived ones;
+/// missing cells have no row
+pub fn rows(conjugation: &Conjugation) -> impl Iterator<Item = Row<'_>> {
+    conjugation
+        .tenses
+        .iter()
+        .chain(&conjugation.derived)
+        .flat_map(|table| {
+            table
+                .cells
+                .iter()
+                .enumerate()
+                .flat_map(move |(index, cell)| {
+                    let [person, number, gender] = cell_features(table.tense, index);
+                    cell.forms
+                        .iter()
+                        .flatten()
+                        .enumerate()
+                        .map(move |(variant, form)| Row {
+                            mood: table.mood.name(),
+                            tense: table.tense.name(),
+                            person,
+                            number,
+                            gender,
+                            form,
+                            variant,
+                        })
+                })
+        })
+}
+
+/// Write the header and one row per form of every verb, conjugating one verb at
+/// a time so that the table is never held in memory. Returns the number of rows.
+pub fn write_table<'a>(
+    verbs: impl IntoIterator<Item = &'a Verb>,
+    templates: &HashMap<String, Template>,
+    format: TableFormat,
+    header: bool,
+    writer: impl Write,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut table = csv::WriterBuilder::new()
+        .delimiter(format.delimiter())
+        .from_writer(writer);
+    if header {
+        table.write_record(HEADER)?;
+    }
+    let options = ConjugationOptions::default();
+    let mut count = 0;
+    for verb in verbs {
+        let Some(conjugation) = conjugation::conjugate(verb, templates, &options) else {
+            continue;
+        };
+        for row in rows(&conjugation) {
+            let variant = row.variant.to_string();
+            table.write_record([
+                verb.verb.as_str(),
+                verb.template.as_str(),
+                row.mood,
+                row.tense,
+                row.person,
+                row.number,
+                row.gender,
+                row.form,
+                variant.as_str(),
+            ])?;
+            count += 1;
+        }
+    }
+    table.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data;
+
+    /// Rows of the table of the named verbs, header first
+    fn table(names: &[&str], format: TableFormat) -> Vec<Vec<String>> {
+        let selected = test_data::verbs(names);
+        let templates = test_data::templates();
+        let mut output = Vec::new();
+        write_table(&selected, &templates, format, true, &mut output).unwrap();
+        let separator = if format == TableFormat::Csv {
+            ','
+        } else {
+            '\t'
+        };
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| line.split(separator).map(str::to_string).collect())
+            .collect()
+    }
+
+    fn find<'a>(rows: &'a [Vec<String>], verb: &str, tense: &str) -> Vec<&'a Vec<String>> {
+        rows.iter()
+            .filter(|row| row[0] == verb && row[3] == tense)
+            .collect()
+    }
+
+    #[test]
+    fn rows_have_the_header_columns() {
+        let rows = table(&["aimer"], TableFormat::Tsv);
+        assert_eq!(rows[0], HEADER);
+        assert!(rows.iter().all(|row| row.len() == HEADER.len()));
+
+        let present = find(&rows, "aimer", "indicative-present");
+        assert_eq!(
+            present[0].as_slice(),
+            [
+                "aimer",
+                "aim:er",
+                "indicative",
+                "indicative-present",
+                "first-singular",
+                "singular",
+                "",
+                "aime",
+                "0"
+            ]
+        );
+        let infinitive = find(&rows, "aimer", "infinitive-present");
+        assert_eq!(infinitive[0][4..7], ["", "", ""]);
+    }
+
+    #[test]
+    fn past_participle_cells_in_gender_then_number_order() {
+        let rows = table(&["aimer"], TableFormat::Csv);
+        let features: Vec<[&str; 3]> = find(&rows, "aimer", "past-participle")
+            .iter()
+            .map(|row| [row[5].as_str(), row[6].as_str(), row[7].as_str()])
+            .collect();
+        assert_eq!(
+            features,
+            [
+                ["singular", "masculine", "aimé"],
+                ["plural", "masculine", "aimés"],
+                ["singular", "feminine", "aimée"],
+                ["plural", "feminine", "aimées"],
+            ]
+        );
+    }
+
+    #[test]
+    fn variants_are_numbered_within_their_cell() {
+        let rows = table(&["asseoir"], TableFormat::Csv);
+        let first: Vec<(&str, &str)> = find(&rows, "asseoir", "indicative-present")
+            .iter()
+            .filter(|row| row[4] == "first-singular")
+            .map(|row| (row[7].as_str(), row[8].as_str()))
+            .collect();
+        assert_eq!(first, [("assieds", "0"), ("assois", "1")]);
+    }
+}
@@ -1,21 +1,532 @@
+use crate::agreement;
+use crate::conjugation;
+use crate::derivation::{self, Derivation};
+use crate::drill;
+use crate::export;
+use crate::imperative;
+use crate::induction;
+use crate::inference::{Inference, TemplateGuesser};
+use crate::pronouns;
+use crate::properties::{self, PropertyFilter, VerbProperties};
+use crate::render::{self, FormatQuery, Table};
+use crate::review::{self, ProgressStore, ReviewError};
+use crate::spelling::{self, Spelling};
 use crate::template;
 use crate::verbs;
+use crate::worksheet;
+use log::error;
 use percent_encoding::percent_decode_str;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::Arc;
 use warp::Rejection;
 use warp::Reply;
 
+/// A looked-up value, with the derivation of unlisted prefixed verbs and a flag
+/// when the verb is not in the dictionary and its template was guessed
+#[derive(Debug, Serialize)]
+struct Resolved<T: Serialize> {
+    #[serde(flatten)]
+    value: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    derivation: Option<Derivation>,
+    inferred: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inference: Option<Inference>,
+}
+
+/// How a requested verb was found
+#[derive(Debug)]
+struct Resolution {
+    verb: verbs::Verb,
+    pronominal: bool,
+    derivation: Option<Derivation>,
+    inference: Option<Inference>,
+}
+
+impl Resolution {
+    fn into_resolved<T: Serialize>(self, value: T) -> Resolved<T> {
+        Resolved {
+            value,
+            derivation: self.derivation,
+            inferred: self.inference.is_some(),
+            inference: self.inference,
+        }
+    }
+}
+
+/// Find a verb in the dictionary, derive it from a listed verb by prefixation,
+/// or guess its template when both fail. A leading reflexive pronoun ("se lever",
+/// "s'asseoir") marks the verb as pronominal.
+fn resolve_verb(
+    verb_name: &str,
+    search_index: &verbs::VerbSearchIndex,
+    guesser: &TemplateGuesser,
+) -> Option<Resolution> {
+    let (verb_name, pronominal) = pronouns::strip_reflexive(verb_name);
+    // Infinitives in the 1990 spelling ("paraitre") find their dictionary entry
+    if let Some(verb) = search_index.find(verb_name) {
+        return Some(Resolution {
+            verb: verb.clone(),
+            pronominal,
+            derivation: None,
+            inference: None,
+        });
+    }
+    if let Some((verb, derivation)) = derivation::derive(verb_name, search_index.verbs()) {
+        return Some(Resolution {
+            verb,
+            pronominal,
+            derivation: Some(derivation),
+            inference: None,
+        });
+    }
+    guesser
+        .guess(verb_name)
+        .map(|(verb, inference)| Resolution {
+            verb,
+            pronominal,
+            derivation: None,
+            inference: Some(inference),
+        })
+}
+
+/// A verb with the properties restricting its conjugation
+#[derive(Debug, Serialize)]
+struct VerbInfo<V: Serialize> {
+    #[serde(flatten)]
+    verb: V,
+    #[serde(flatten)]
+    properties: VerbProperties,
+    /// Set when the verb was looked up with a reflexive pronoun ("se lever")
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pronominal: bool,
+    /// 1990 spelling of the infinitive, when requested and different
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reformed: Option<String>,
+}
+
+/// 1990 spelling of an infinitive when that spelling is requested and differs
+fn reformed_infinitive(verb: &str, spelling: Spelling) -> Option<String> {
+    match spelling {
+        Spelling::Traditional => None,
+        Spelling::Reformed | Spelling::Both => spelling::reformed_infinitive(verb),
+    }
+}
+
+/// Properties of a looked-up verb, for text representations
+fn verb_table(resolved: &Resolved<VerbInfo<verbs::Verb>>) -> Vec<Table> {
+    let yes_no = |flag: bool| if flag { "yes" } else { "no" }.to_string();
+    let info = &resolved.value;
+    let mut rows = vec![
+        vec!["Template".to_string(), info.verb.template.clone()],
+        vec!["Aspirate h".to_string(), yes_no(info.verb.aspirate_h)],
+        vec!["Impersonal".to_string(), yes_no(info.properties.impersonal)],
+        vec!["Defective".to_string(), yes_no(info.properties.defective)],
+    ];
+    if let Some(reformed) = &info.reformed {
+        rows.push(vec!["1990 spelling".to_string(), reformed.clone()]);
+    }
+    if info.pronominal {
+        rows.push(vec!["Pronominal".to_string(), yes_no(true)]);
+    }
+    if let Some(derivation) = &resolved.derivation {
+        rows.push(vec!["Derived from".to_string(), derivation.base.clone()]);
+    }
+    if let Some(inference) = &resolved.inference {
+        rows.push(vec![
+            "Inferred".to_string(),
+            format!(
+                "from -{} ({:.0}%)",
+                inference.ending,
+                inference.confidence * 100.0
+            ),
+        ]);
+    }
+    vec![Table {
+        caption: None,
+        header: vec!["Property".to_string(), "Value".to_string()],
+        rows,
+    }]
+}
+
+fn not_acceptable() -> warp::reply::Response {
+    warp::reply::with_status("", warp::http::StatusCode::NOT_ACCEPTABLE).into_response()
+}
+
 pub async fn get_verb_handler(
     verb_name: String,
+    format: FormatQuery,
+    spelling: Spelling,
+    accept: Option<String>,
+    search_index: Arc<verbs::VerbSearchIndex>,
+    templates: Arc<HashMap<String, template::Template>>,
+    guesser: Arc<TemplateGuesser>,
+) -> Result<warp::reply::Response, Rejection> {
+    let Some(format) = render::negotiate(format, accept.as_deref()) else {
+        return Ok(not_acceptable());
+    };
+    // Decode URL-encoded verb name (e.g., %C3%AAtre -> être)
+    let decoded_name = percent_decode_str(&verb_name)
+        .decode_utf8()
+        .map_err(|_| warp::reject::not_found())?
+        .to_string();
+
+    match resolve_verb(&decoded_name, &search_index, &guesser) {
+        Some(resolution) => {
+            let info = VerbInfo {
+                verb: resolution.verb.clone(),
+                properties: properties::properties(&resolution.verb, &templates),
+                pronominal: resolution.pronominal,
+                reformed: reformed_infinitive(&resolution.verb.verb, spelling),
+            };
+            let title = resolution.verb.verb.clone();
+            let resolved = resolution.into_resolved(info);
+            Ok(render::reply(format, &resolved, &title, verb_table))
+        }
+        None => Ok(warp::reply::with_status("", warp::http::StatusCode::NOT_FOUND).into_response()),
+    }
+}
+
+pub async fn get_conjugation_handler(
+    verb_name: String,
+    options: conjugation::ConjugationOptions,
+    format: FormatQuery,
+    accept: Option<String>,
+    search_index: Arc<verbs::VerbSearchIndex>,
+    templates: Arc<HashMap<String, template::Template>>,
+    guesser: Arc<TemplateGuesser>,
+) -> Result<warp::reply::Response, Rejection> {
+    let Some(format) = render::negotiate(format, accept.as_deref()) else {
+        return Ok(not_acceptable());
+    };
+    let decoded_name = percent_decode_str(&verb_name)
+        .decode_utf8()
+        .map_err(|_| warp::reject::not_found())?
+        .to_string();
+
+    let Some(resolution) = resolve_verb(&decoded_name, &search_index, &guesser) else {
+        return Ok(warp::reply::with_status("", warp::http::StatusCode::NOT_FOUND).into_response());
+    };
+    let options = conjugation::ConjugationOptions {
+        pronominal: options.pronominal || resolution.pronominal,
+        derived_from: resolution
+            .derivation
+            .as_ref()
+            .map(|derivation| derivation.base.clone()),
+        ..options
+    };
+    if options.voice == conjugation::Voice::Passive
+        && let Some(reason) = options.passive_restriction(&resolution.verb.verb)
+    {
+        return Ok(warp::reply::with_status(
+            format!("{}: {}", resolution.verb.verb, reason),
+            warp::http::StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response());
+    }
+
+    match conjugation::conjugate(&resolution.verb, &templates, &options) {
+        Some(conjugation) => {
+            let title = conjugation.verb.clone();
+            let resolved = resolution.into_resolved(conjugation);
+            Ok(render::reply(format, &resolved, &title, |resolved| {
+                render::conjugation_tables(&resolved.value)
+            }))
+        }
+        None => Ok(warp::reply::with_status("", warp::http::StatusCode::NOT_FOUND).into_response()),
+    }
+}
+
+pub async fn get_agreement_handler(
+    verb_name: String,
+    query: agreement::AgreementQuery,
+    search_index: Arc<verbs::VerbSearchIndex>,
+    templates: Arc<HashMap<String, template::Template>>,
+    guesser: Arc<TemplateGuesser>,
+) -> Result<warp::reply::Response, Rejection> {
+    let decoded_name = percent_decode_str(&verb_name)
+        .decode_utf8()
+        .map_err(|_| warp::reject::not_found())?
+        .to_string();
+
+    let agreement = resolve_verb(&decoded_name, &search_index, &guesser).and_then(|resolution| {
+        let query = agreement::AgreementQuery {
+            pronominal: query.pronominal || resolution.pronominal,
+            ..query
+        };
+        let agreement = agreement::agree(&resolution.verb, &templates, &query)?;
+        Some(resolution.into_resolved(agreement))
+    });
+
+    match agreement {
+        Some(resolved) => Ok(warp::reply::json(&resolved).into_response()),
+        None => Ok(warp::reply::with_status("", warp::http::StatusCode::NOT_FOUND).into_response()),
+    }
+}
+
+pub async fn get_imperative_handler(
+    verb_name: String,
+    query: imperative::ImperativeQuery,
+    search_index: Arc<verbs::VerbSearchIndex>,
+    templates: Arc<HashMap<String, template::Template>>,
+    guesser: Arc<TemplateGuesser>,
+) -> Result<warp::reply::Response, Rejection> {
+    let decoded_name = percent_decode_str(&verb_name)
+        .decode_utf8()
+        .map_err(|_| warp::reject::not_found())?
+        .to_string();
+
+    let Some(resolution) = resolve_verb(&decoded_name, &search_index, &guesser) else {
+        return Ok(warp::reply::with_status("", warp::http::StatusCode::NOT_FOUND).into_response());
+    };
+    let query = imperative::ImperativeQuery {
+        pronominal: query.pronominal || resolution.pronominal,
+        ..query
+    };
+
+    match imperative::imperative(&resolution.verb, &templates, &query) {
+        Ok(imperative) => {
+            Ok(warp::reply::json(&resolution.into_resolved(imperative)).into_response())
+        }
+        Err(message) => Ok(warp::reply::with_status(
+            message,
+            warp::http::StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response()),
+    }
+}
+
+pub async fn get_drill_handler(
+    query: drill::DrillQuery,
+    verbs: Arc<Vec<verbs::Verb>>,
+    templates: Arc<HashMap<String, template::Template>>,
+) -> Result<warp::reply::Response, Rejection> {
+    match drill::pick(&verbs, &templates, &query) {
+        Ok(drill) => Ok(warp::reply::json(&drill).into_response()),
+        Err(message) => Ok(warp::reply::with_status(
+            message,
+            warp::http::StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response()),
+    }
+}
+
+pub async fn check_drill_handler(
+    answer: drill::DrillAnswer,
+    search_index: Arc<verbs::VerbSearchIndex>,
+    templates: Arc<HashMap<String, template::Template>>,
+    guesser: Arc<TemplateGuesser>,
+) -> Result<warp::reply::Response, Rejection> {
+    let Some(resolution) = resolve_verb(&answer.verb, &search_index, &guesser) else {
+        return Ok(warp::reply::with_status("", warp::http::StatusCode::NOT_FOUND).into_response());
+    };
+
+    match drill::check(&resolution.verb, &templates, &answer) {
+        Ok(result) => Ok(warp::reply::json(&result).into_response()),
+        Err(message) => Ok(warp::reply::with_status(
+            message,
+            warp::http::StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response()),
+    }
+}
+
+/// Learner token of an "Authorization: Bearer <token>" header
+fn bearer_token(authorization: &Option<String>) -> Option<&str> {
+    authorization
+        .as_deref()
+        .and_then(|header| header.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+fn review_error_response(error: ReviewError) -> warp::reply::Response {
+    match error {
+        ReviewError::Unauthorized => warp::reply::with_status(
+            "missing or unknown learner token".to_string(),
+            warp::http::StatusCode::UNAUTHORIZED,
+        )
+        .into_response(),
+        ReviewError::Invalid(message) => {
+            warp::reply::with_status(message, warp::http::StatusCode::UNPROCESSABLE_ENTITY)
+                .into_response()
+        }
+        ReviewError::Storage(message) => {
+            error!("Progress database error: {}", message);
+            warp::reply::with_status(
+                "progress database error".to_string(),
+                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            )
+            .into_response()
+        }
+    }
+}
+
+/// Run a review operation on the blocking thread pool, as the progress store
+/// locks its SQLite connection for the length of each query
+async fn blocking_review<T: Send + 'static>(
+    operation: impl FnOnce() -> Result<T, ReviewError> + Send + 'static,
+) -> Result<T, ReviewError> {
+    tokio::task::spawn_blocking(operation)
+        .await
+        .unwrap_or_else(|error| Err(ReviewError::Storage(error.to_string())))
+}
+
+pub async fn create_learner_handler(
+    store: Arc<ProgressStore>,
+) -> Result<warp::reply::Response, Rejection> {
+    match blocking_review(move || store.create_learner()).await {
+        Ok(token) => Ok(warp::reply::json(&serde_json::json!({ "token": token })).into_response()),
+        Err(error) => Ok(review_error_response(error)),
+    }
+}
+
+pub async fn next_review_handler(
+    query: drill::DrillQuery,
+    authorization: Option<String>,
+    store: Arc<ProgressStore>,
     verbs: Arc<Vec<verbs::Verb>>,
+    templates: Arc<HashMap<String, template::Template>>,
 ) -> Result<warp::reply::Response, Rejection> {
-    match verbs.binary_search_by(|v| v.verb.cmp(&verb_name)) {
-        Ok(index) => Ok(warp::reply::json(&verbs[index]).into_response()),
-        Err(_) => {
-            Ok(warp::reply::with_status("", warp::http::StatusCode::NOT_FOUND).into_response())
+    let next = blocking_review(move || {
+        review::next(
+            &store,
+            bearer_token(&authorization),
+            &verbs,
+            &templates,
+            &query,
+        )
+    });
+    match next.await {
+        Ok(item) => Ok(warp::reply::json(&item).into_response()),
+        Err(error) => Ok(review_error_response(error)),
+    }
+}
+
+pub async fn answer_review_handler(
+    answer: drill::DrillAnswer,
+    authorization: Option<String>,
+    store: Arc<ProgressStore>,
+    search_index: Arc<verbs::VerbSearchIndex>,
+    templates: Arc<HashMap<String, template::Template>>,
+    guesser: Arc<TemplateGuesser>,
+) -> Result<warp::reply::Response, Rejection> {
+    let Some(resolution) = resolve_verb(&answer.verb, &search_index, &guesser) else {
+        return Ok(warp::reply::with_status("", warp::http::StatusCode::NOT_FOUND).into_response());
+    };
+
+    let outcome = blocking_review(move || {
+        review::answer(
+            &store,
+            bearer_token(&authorization),
+            &resolution.verb,
+            &templates,
+            &answer,
+        )
+    });
+    match outcome.await {
+        Ok(outcome) => Ok(warp::reply::json(&outcome).into_response()),
+        Err(error) => Ok(review_error_response(error)),
+    }
+}
+
+pub async fn review_stats_handler(
+    authorization: Option<String>,
+    store: Arc<ProgressStore>,
+) -> Result<warp::reply::Response, Rejection> {
+    match blocking_review(move || review::stats(&store, bearer_token(&authorization))).await {
+        Ok(stats) => Ok(warp::reply::json(&stats).into_response()),
+        Err(error) => Ok(review_error_response(error)),
+    }
+}
+
+pub async fn get_worksheet_handler(
+    query: worksheet::WorksheetQuery,
+    verbs: Arc<Vec<verbs::Verb>>,
+    templates: Arc<HashMap<String, template::Template>>,
+) -> Result<warp::reply::Response, Rejection> {
+    let sheet = match worksheet::generate(&verbs, &templates, &query) {
+        Ok(sheet) => sheet,
+        Err(message) => {
+            return Ok(warp::reply::with_status(
+                message,
+                warp::http::StatusCode::UNPROCESSABLE_ENTITY,
+            )
+            .into_response());
+        }
+    };
+    let (document, content_type) = match query.format {
+        worksheet::WorksheetFormat::Html => (
+            worksheet::render_html(&sheet).into_bytes(),
+            "text/html; charset=utf-8",
+        ),
+        worksheet::WorksheetFormat::Pdf => (worksheet::render_pdf(&sheet), "application/pdf"),
+    };
+    let mut response = warp::reply::Response::new(document.into());
+    response.headers_mut().insert(
+        "content-type",
+        warp::http::HeaderValue::from_static(content_type),
+    );
+    Ok(response)
+}
+
+/// One page of the inflected lexicon, written on the blocking thread pool. The
+/// table is paged over verbs so that a response never holds more than a page;
+/// a `Link` header points to the next one.
+pub async fn export_handler(
+    query: export::ExportQuery,
+    verbs: Arc<Vec<verbs::Verb>>,
+    templates: Arc<HashMap<String, template::Template>>,
+) -> Result<warp::reply::Response, Rejection> {
+    let limit = query.limit.unwrap_or(export::DEFAULT_PAGE_SIZE);
+    if limit == 0 || limit > export::MAX_PAGE_SIZE {
+        return Ok(warp::reply::with_status(
+            format!("limit must be between 1 and {}", export::MAX_PAGE_SIZE),
+            warp::http::StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response());
+    }
+    let offset = query.offset;
+    let format = query.format;
+    let page_verbs = verbs.clone();
+    let table = tokio::task::spawn_blocking(move || {
+        let mut table = Vec::new();
+        let page = page_verbs.iter().skip(offset).take(limit);
+        export::write_table(page, &templates, format, offset == 0, &mut table)
+            .map(|_| table)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    let table = match table {
+        Ok(table) => table,
+        Err(e) => {
+            error!("Export failed: {}", e);
+            return Ok(
+                warp::reply::with_status("", warp::http::StatusCode::INTERNAL_SERVER_ERROR)
+                    .into_response(),
+            );
+        }
+    };
+    let mut response = warp::reply::Response::new(table.into());
+    let headers = response.headers_mut();
+    headers.insert(
+        "content-type",
+        warp::http::HeaderValue::from_static(format.content_type()),
+    );
+    let next = offset.saturating_add(limit);
+    if next < verbs.len() {
+        let format = match format {
+            export::TableFormat::Csv => "csv",
+            export::TableFormat::Tsv => "tsv",
+        };
+        let link =
+            format!("</api/export?format={format}&offset={next}&limit={limit}>; rel=\"next\"");
+        if let Ok(value) = warp::http::HeaderValue::from_str(&link) {
+            headers.insert("link", value);
         }
     }
+    Ok(response)
 }
 
 pub async fn get_template_handler(
@@ -29,15 +540,284 @@ pub async fn get_template_handler(
         .to_string();
 
     match templates.get(&decoded_name) {
-        Some(template) => Ok(warp::reply::json(template).into_response()),
+        Some(template) => Ok(warp::reply::json(&template.with_placeholders()).into_response()),
         None => Ok(warp::reply::with_status("", warp::http::StatusCode::NOT_FOUND).into_response()),
     }
 }
 
 pub async fn search_verbs_handler(
     query: String,
+    filter: PropertyFilter,
+    spelling: Spelling,
     search_index: Arc<verbs::VerbSearchIndex>,
+    templates: Arc<HashMap<String, template::Template>>,
 ) -> Result<warp::reply::Response, Rejection> {
-    let results = search_index.search(&query);
+    // "se lever" and "s'asseoir" search for the bare infinitive
+    let (query, pronominal) = pronouns::strip_reflexive(&query);
+    let results: Vec<VerbInfo<&verbs::Verb>> = search_index
+        .search_by(query, |verb| {
+            filter.matches(&properties::properties(verb, &templates))
+        })
+        .into_iter()
+        .map(|verb| VerbInfo {
+            verb,
+            properties: properties::properties(verb, &templates),
+            pronominal,
+            reformed: reformed_infinitive(&verb.verb, spelling),
+        })
+        .collect();
     Ok(warp::reply::json(&results).into_response())
 }
+
+pub async fn induce_template_handler(
+    paradigm: template::Template,
+    templates: Arc<HashMap<String, template::Template>>,
+) -> Result<warp::reply::Response, Rejection> {
+    match induction::induce(&paradigm, &templates) {
+        Ok(induction) => Ok(warp::reply::json(&induction).into_response()),
+        Err(message) => Ok(warp::reply::with_status(
+            message,
+            warp::http::StatusCode::UNPROCESSABLE_ENTITY,
+        )
+        .into_response()),
+    }
+}
+
+/// Answer API requests whose query string or JSON body could not be parsed
+/// with 400 instead of letting them fall through to the single-page app
+pub async fn handle_api_rejection(
+    rejection: Rejection,
+) -> Result<warp::reply::Response, Rejection> {
+    let message = if let Some(error) = rejection.find::<warp::reject::InvalidQuery>() {
+        error.to_string()
+    } else if let Some(error) = rejection.find::<warp::filters::body::BodyDeserializeError>() {
+        error.to_string()
+    } else {
+        return Err(rejection);
+    };
+    Ok(warp::reply::with_status(message, warp::http::StatusCode::BAD_REQUEST).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::{Deserializer, IgnoredAny, MapAccess, Visitor};
+
+    const VERBS_XML: &str = include_str!("../../data/verbs-fr.xml");
+    const CONJUGATION_XML: &str = include_str!("../../data/conjugation-fr.xml");
+
+    /// Keys of a JSON object in order, duplicates included
+    struct Keys(Vec<String>);
+
+    impl<'de> serde::Deserialize<'de> for Keys {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct KeysVisitor;
+            impl<'de> Visitor<'de> for KeysVisitor {
+                type Value = Keys;
+                fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    formatter.write_str("a JSON object")
+                }
+                fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Keys, A::Error> {
+                    let mut keys = Vec::new();
+                    while let Some(key) = map.next_key::<String>()? {
+                        map.next_value::<IgnoredAny>()?;
+                        keys.push(key);
+                    }
+                    Ok(Keys(keys))
+                }
+            }
+            deserializer.deserialize_map(KeysVisitor)
+        }
+    }
+
+    fn assert_unique_keys(value: &impl Serialize) {
+        let json = serde_json::to_string(value).unwrap();
+        let Keys(keys) = serde_json::from_str(&json).unwrap();
+        for key in &keys {
+            let count = keys.iter().filter(|other| *other == key).count();
+            assert_eq!(count, 1, "{key} appears {count} times in {json}");
+        }
+    }
+
+    #[test]
+    fn pronominal_conjugation_has_unique_keys() {
+        let verbs = verbs::load_all_verbs(VERBS_XML).unwrap();
+        let templates = template::load_all_templates(CONJUGATION_XML).unwrap();
+        let guesser = TemplateGuesser::new(&verbs, &templates);
+        let search_index = verbs::build_search_index(verbs);
+
+        let resolution = resolve_verb("se lever", &search_index, &guesser).unwrap();
+        assert!(resolution.pronominal);
+        let options = conjugation::ConjugationOptions {
+            pronominal: resolution.pronominal,
+            ..conjugation::ConjugationOptions::default()
+        };
+        let conjugation = conjugation::conjugate(&resolution.verb, &templates, &options).unwrap();
+        let resolved = resolution.into_resolved(conjugation);
+        assert_unique_keys(&resolved);
+        let json = serde_json::to_value(&resolved).unwrap();
+        assert_eq!(json["pronominal"], true);
+
+        let resolution = resolve_verb("se lever", &search_index, &guesser).unwrap();
+        let info = VerbInfo {
+            verb: resolution.verb.clone(),
+            properties: properties::properties(&resolution.verb, &templates),
+            pronominal: resolution.pronominal,
+            reformed: None,
+        };
+        let resolved = resolution.into_resolved(info);
+        assert_unique_keys(&resolved);
+        let json = serde_json::to_value(&resolved).unwrap();
+        assert_eq!(json["pronominal"], true);
+    }
+
+    #[test]
+    fn pronominal_agreement_has_unique_keys() {
+        let verbs = verbs::load_all_verbs(VERBS_XML).unwrap();
+        let templates = template::load_all_templates(CONJUGATION_XML).unwrap();
+        let guesser = TemplateGuesser::new(&verbs, &templates);
+        let search_index = verbs::build_search_index(verbs);
+
+        let resolution = resolve_verb("se lever", &search_index, &guesser).unwrap();
+        let query = agreement::AgreementQuery {
+            pronominal: resolution.pronominal,
+            gender: conjugation::Gender::Feminine,
+            ..agreement::AgreementQuery::default()
+        };
+        let agreement = agreement::agree(&resolution.verb, &templates, &query).unwrap();
+        let resolved = resolution.into_resolved(agreement);
+        assert_unique_keys(&resolved);
+        let json = serde_json::to_value(&resolved).unwrap();
+        assert_eq!(json["pronominal"], true);
+        assert_eq!(json["auxiliary"], "être");
+        assert_eq!(json["participles"][0], "levée");
+    }
+
+    #[tokio::test]
+    async fn verb_responses_follow_the_accept_header() {
+        let verbs = verbs::load_all_verbs(VERBS_XML).unwrap();
+        let templates = template::load_all_templates(CONJUGATION_XML).unwrap();
+        let guesser = Arc::new(TemplateGuesser::new(&verbs, &templates));
+        let search_index = Arc::new(verbs::build_search_index(verbs));
+        let templates = Arc::new(templates);
+        let get = |accept: &str| {
+            get_verb_handler(
+                "aimer".to_string(),
+                FormatQuery::default(),
+                Spelling::default(),
+                Some(accept.to_string()),
+                search_index.clone(),
+                templates.clone(),
+                guesser.clone(),
+            )
+        };
+
+        let response = get("image/png").await.unwrap();
+        assert_eq!(response.status(), warp::http::StatusCode::NOT_ACCEPTABLE);
+        let response = get("image/png, text/markdown;q=0.5").await.unwrap();
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        assert_eq!(
+            response.headers()["content-type"],
+            "text/markdown; charset=utf-8"
+        );
+    }
+
+    /// Body of a response, read frame by frame
+    async fn body(response: warp::reply::Response) -> Vec<u8> {
+        use warp::hyper::body::Body;
+        let mut body = response.into_body();
+        let mut bytes = Vec::new();
+        while let Some(frame) =
+            std::future::poll_fn(|cx| std::pin::Pin::new(&mut body).poll_frame(cx)).await
+        {
+            if let Ok(data) = frame.unwrap().into_data() {
+                bytes.extend_from_slice(&data);
+            }
+        }
+        bytes
+    }
+
+    #[tokio::test]
+    async fn export_without_limit_is_one_page() {
+        let verbs = Arc::new(verbs::load_all_verbs(VERBS_XML).unwrap());
+        let templates = Arc::new(template::load_all_templates(CONJUGATION_XML).unwrap());
+
+        let query = export::ExportQuery::default();
+        let response = export_handler(query, verbs.clone(), templates.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), warp::http::StatusCode::OK);
+        let next = "</api/export?format=csv&offset=500&limit=500>; rel=\"next\"";
+        assert_eq!(response.headers()["link"], next);
+        let table = String::from_utf8(body(response).await).unwrap();
+        let mut exported: Vec<&str> = table
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').next().unwrap())
+            .collect();
+        exported.dedup();
+        let first: Vec<&str> = verbs[..export::DEFAULT_PAGE_SIZE]
+            .iter()
+            .map(|verb| verb.verb.as_str())
+            .collect();
+        assert_eq!(exported, first);
+
+        for limit in [0, export::MAX_PAGE_SIZE + 1] {
+            let query = export::ExportQuery {
+                limit: Some(limit),
+                ..export::ExportQuery::default()
+            };
+            let response = export_handler(query, verbs.clone(), templates.clone())
+                .await
+                .unwrap();
+            assert_eq!(
+                response.status(),
+                warp::http::StatusCode::UNPROCESSABLE_ENTITY
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn passive_voice_of_derived_and_unlisted_verbs() {
+        let verbs = verbs::load_all_verbs(VERBS_XML).unwrap();
+        let templates = template::load_all_templates(CONJUGATION_XML).unwrap();
+        let guesser = Arc::new(TemplateGuesser::new(&verbs, &templates));
+        let search_index = Arc::new(verbs::build_search_index(verbs));
+        let templates = Arc::new(templates);
+        let passive = |verb: &str| {
+            let options = conjugation::ConjugationOptions {
+                voice: conjugation::Voice::Passive,
+                ..conjugation::ConjugationOptions::default()
+            };
+            get_conjugation_handler(
+                verb.to_string(),
+                options,
+                FormatQuery::default(),
+                None,
+                search_index.clone(),
+                templates.clone(),
+                guesser.clone(),
+            )
+        };
+
+        for verb in ["refaire", "redécouvrir"] {
+            let response = passive(verb).await.unwrap();
+            assert_eq!(response.status(), warp::http::StatusCode::OK, "{verb}");
+        }
+        for verb in ["marcher", "blurpiner"] {
+            let response = passive(verb).await.unwrap();
+            assert_eq!(
+                response.status(),
+                warp::http::StatusCode::UNPROCESSABLE_ENTITY,
+                "{verb}"
+            );
+            let message = String::from_utf8(body(response).await).unwrap();
+            assert_eq!(
+                message,
+                format!(
+                    "{verb}: only verbs on the list of verbs taking a direct object have a passive voice"
+                )
+            );
+        }
+    }
+}
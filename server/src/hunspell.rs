@@ -0,0 +1,124 @@
+use crate::conjugation::{self, Tense};
+use crate::template::Template;
+use crate::verbs::Verb;
+use std::collections::{HashMap, HashSet};
+
+/// Affix and dictionary files of a Hunspell dictionary
+#[derive(Debug, Clone)]
+pub struct Hunspell {
+    pub aff: String,
+    pub dic: String,
+}
+
+/// Distinct endings of the simple tenses of a template, in template order.
+/// Endings made of several words cannot be expressed as affixes and are left out.
+fn endings(template: &Template) -> Vec<String> {
+    let mut seen = HashSet::new();
+    Tense::SIMPLE
+        .iter()
+        .flat_map(|&tense| conjugation::endings(template, tense))
+        .flatten()
+        .filter(|ending| !ending.contains(char::is_whitespace))
+        .filter(|ending| seen.insert(ending.clone()))
+        .collect()
+}
+
+/// Empty strip and affix strings are written "0" in affix files
+fn affix_string(text: &str) -> &str {
+    if text.is_empty() { "0" } else { text }
+}
+
+/// Build the dictionary: one suffix class per template, numbered in template
+/// name order, whose rules strip the template suffix from the infinitive and
+/// add an ending ("aim:er" turns "aimer" into "aimons" with `SFX 11 er ons er`),
+/// and one dictionary entry per verb, its infinitive flagged with its template.
+/// Verbs whose template is missing or does not end their infinitive are left out.
+pub fn generate(verbs: &[Verb], templates: &HashMap<String, Template>) -> Hunspell {
+    let mut names: Vec<&String> = templates.keys().collect();
+    names.sort();
+
+    let mut aff = String::from("SET UTF-8\nFLAG num\n");
+    let mut flags = HashMap::new();
+    for (flag, name) in (1..).zip(names) {
+        let suffix = conjugation::template_suffix(name);
+        let endings = endings(&templates[name]);
+        flags.insert(name.as_str(), flag);
+        aff.push_str(&format!("\n# {name}\nSFX {flag} Y {}\n", endings.len()));
+        for ending in endings {
+            aff.push_str(&format!(
+                "SFX {flag} {} {} {}\n",
+                affix_string(suffix),
+                affix_string(&ending),
+                if suffix.is_empty() { "." } else { suffix }
+            ));
+        }
+    }
+
+    let entries: Vec<String> = verbs
+        .iter()
+        .filter(|verb| conjugation::stem(&verb.verb, &verb.template).is_some())
+        .filter_map(|verb| {
+            let flag = flags.get(verb.template.as_str())?;
+            Some(format!("{}/{flag}\n", verb.verb))
+        })
+        .collect();
+    let mut dic = format!("{}\n", entries.len());
+    dic.extend(entries);
+
+    Hunspell { aff, dic }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data;
+
+    /// Expand every dictionary entry with its suffix rules, the way Hunspell does
+    fn expand(hunspell: &Hunspell) -> HashMap<String, HashSet<String>> {
+        let mut rules: HashMap<&str, Vec<(&str, &str, &str)>> = HashMap::new();
+        for line in hunspell.aff.lines() {
+            let fields: Vec<&str> = line.split(' ').collect();
+            if let ["SFX", flag, strip, add, condition] = fields[..] {
+                let strip = if strip == "0" { "" } else { strip };
+                let add = if add == "0" { "" } else { add };
+                rules.entry(flag).or_default().push((strip, add, condition));
+            }
+        }
+        hunspell
+            .dic
+            .lines()
+            .skip(1)
+            .map(|line| {
+                let (word, flag) = line.split_once('/').unwrap();
+                let mut forms = HashSet::from([word.to_string()]);
+                for &(strip, add, condition) in &rules[flag] {
+                    if condition == "." || word.ends_with(condition) {
+                        let stem = word.strip_suffix(strip).unwrap();
+                        forms.insert(format!("{stem}{add}"));
+                    }
+                }
+                (word.to_string(), forms)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn affixes_expand_to_simple_forms() {
+        let verbs = test_data::all_verbs();
+        let templates = test_data::templates();
+        let expanded = expand(&generate(&verbs, &templates));
+
+        assert_eq!(expanded.len(), verbs.len());
+        for verb in &verbs {
+            let forms = conjugation::simple_forms(verb, &templates[&verb.template]).unwrap();
+            let mut expected: HashSet<String> = forms
+                .into_values()
+                .flatten()
+                .flatten()
+                .filter(|form| !form.contains(char::is_whitespace))
+                .collect();
+            expected.insert(verb.verb.clone());
+            assert_eq!(expanded[&verb.verb], expected, "forms of {}", verb.verb);
+        }
+    }
+}
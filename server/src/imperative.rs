@@ -0,0 +1,441 @@
+use crate::conjugation::{self, MissingReason, Person, Tense};
+use crate::pronouns;
+use crate::properties;
+use crate::template::Template;
+use crate::verbs::Verb;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Direct object pronoun of an imperative
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DirectObject {
+    Le,
+    La,
+    Les,
+    Me,
+    Te,
+    Nous,
+    Vous,
+}
+
+/// Indirect object pronoun of an imperative
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndirectObject {
+    Me,
+    Te,
+    Lui,
+    Nous,
+    Vous,
+    Leur,
+}
+
+/// Object pronouns attached to an imperative, read from the query string
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ImperativeQuery {
+    pub direct: Option<DirectObject>,
+    pub indirect: Option<IndirectObject>,
+    pub y: bool,
+    pub en: bool,
+    pub pronominal: bool,
+}
+
+/// Affirmative and negative imperative of one person
+#[derive(Debug, Clone, Serialize)]
+pub struct ImperativeCell {
+    pub person: Person,
+    pub affirmative: Option<Vec<String>>,
+    pub negative: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub missing: Option<MissingReason>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Imperative {
+    pub verb: String,
+    pub pronominal: bool,
+    pub cells: Vec<ImperativeCell>,
+}
+
+/// Object pronouns in their order before the verb (negative imperative):
+/// me/te/nous/vous, le/la/les, lui/leur, y, en
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Clitic {
+    /// me, te, nous, vous, as direct, indirect or reflexive object
+    Personal(Person),
+    Third(&'static str),
+    Dative(&'static str),
+    Y,
+    En,
+}
+
+impl Clitic {
+    /// Form before the verb ("ne me le donne pas")
+    fn unstressed(self) -> &'static str {
+        match self {
+            Clitic::Personal(person) => pronouns::reflexive_pronoun(person),
+            Clitic::Third(pronoun) | Clitic::Dative(pronoun) => pronoun,
+            Clitic::Y => "y",
+            Clitic::En => "en",
+        }
+    }
+
+    /// Form after the verb, where "me" and "te" become "moi" and "toi"
+    /// ("donne-le-moi") unless elided before "y" or "en" ("donne-m'en")
+    fn after_verb(self, next: Option<Clitic>) -> String {
+        let before_vowel = matches!(next, Some(Clitic::Y | Clitic::En));
+        match self {
+            Clitic::Personal(Person::FirstSingular) if before_vowel => "m'".to_string(),
+            Clitic::Personal(Person::SecondSingular) if before_vowel => "t'".to_string(),
+            Clitic::Personal(person) => pronouns::stressed_reflexive_pronoun(person).to_string(),
+            Clitic::Third("le" | "la") if before_vowel => "l'".to_string(),
+            clitic => clitic.unstressed().to_string(),
+        }
+    }
+}
+
+fn personal(object: DirectObject) -> Option<Person> {
+    match object {
+        DirectObject::Me => Some(Person::FirstSingular),
+        DirectObject::Te => Some(Person::SecondSingular),
+        DirectObject::Nous => Some(Person::FirstPlural),
+        DirectObject::Vous => Some(Person::SecondPlural),
+        DirectObject::Le | DirectObject::La | DirectObject::Les => None,
+    }
+}
+
+/// Clitics requested by the query for the given imperative person, in the
+/// order they take before the verb
+fn clitics(query: &ImperativeQuery, person: Person) -> Result<Vec<Clitic>, String> {
+    let mut clitics = Vec::new();
+    let mut personal_count = 0;
+    if query.pronominal {
+        clitics.push(Clitic::Personal(person));
+        personal_count += 1;
+    }
+    if let Some(object) = query.direct {
+        match personal(object) {
+            Some(person) => {
+                clitics.push(Clitic::Personal(person));
+                personal_count += 1;
+            }
+            None => clitics.push(Clitic::Third(match object {
+                DirectObject::Le => "le",
+                DirectObject::La => "la",
+                _ => "les",
+            })),
+        }
+    }
+    match query.indirect {
+        Some(IndirectObject::Lui) => clitics.push(Clitic::Dative("lui")),
+        Some(IndirectObject::Leur) => clitics.push(Clitic::Dative("leur")),
+        Some(IndirectObject::Me) => {
+            clitics.insert(0, Clitic::Personal(Person::FirstSingular));
+            personal_count += 1;
+        }
+        Some(IndirectObject::Te) => {
+            clitics.insert(0, Clitic::Personal(Person::SecondSingular));
+            personal_count += 1;
+        }
+        Some(IndirectObject::Nous) => {
+            clitics.insert(0, Clitic::Personal(Person::FirstPlural));
+            personal_count += 1;
+        }
+        Some(IndirectObject::Vous) => {
+            clitics.insert(0, Clitic::Personal(Person::SecondPlural));
+            personal_count += 1;
+        }
+        None => {}
+    }
+    if personal_count > 1 {
+        return Err(
+            "at most one of me, te, nous, vous and the reflexive pronoun can be used".into(),
+        );
+    }
+    // "présente-moi à lui", not "*présente-moi-lui"
+    if personal_count == 1 && clitics.iter().any(|c| matches!(c, Clitic::Dative(_))) {
+        return Err(
+            "lui and leur cannot be combined with me, te, nous, vous or a reflexive pronoun".into(),
+        );
+    }
+    if query.y {
+        clitics.push(Clitic::Y);
+    }
+    if query.en {
+        clitics.push(Clitic::En);
+    }
+    Ok(clitics)
+}
+
+/// "donne-le-moi", "vas-y", "manges-en": objects follow the verb with hyphens
+fn affirmative(form: &str, person: Person, clitics: &[Clitic]) -> String {
+    // Object pronouns after the verb: le/la/les, then moi/toi/nous/vous/lui/leur
+    let mut order: Vec<Clitic> = clitics
+        .iter()
+        .copied()
+        .filter(|c| matches!(c, Clitic::Third(_)))
+        .collect();
+    order.extend(
+        clitics
+            .iter()
+            .copied()
+            .filter(|c| matches!(c, Clitic::Personal(_) | Clitic::Dative(_))),
+    );
+    order.extend(
+        clitics
+            .iter()
+            .copied()
+            .filter(|c| matches!(c, Clitic::Y | Clitic::En)),
+    );
+
+    // Euphonic s of "-e" forms and "va" directly before y or en
+    let mut text = form.to_string();
+    if person == Person::SecondSingular
+        && matches!(order.first(), Some(Clitic::Y | Clitic::En))
+        && (form.ends_with('e') || form == "va")
+    {
+        text.push('s');
+    }
+    for (index, clitic) in order.iter().enumerate() {
+        if !text.ends_with('\'') {
+            text.push('-');
+        }
+        text.push_str(&clitic.after_verb(order.get(index + 1).copied()));
+    }
+    text
+}
+
+/// "ne me le donne pas", "n'y va pas": objects precede the verb
+fn negative(form: &str, clitics: &[Clitic], aspirate_h: bool) -> String {
+    let mut text = form.to_string();
+    let mut aspirate_h = aspirate_h;
+    for clitic in clitics.iter().rev() {
+        text = pronouns::elide(clitic.unstressed(), &text, aspirate_h);
+        aspirate_h = false;
+    }
+    format!("{} pas", pronouns::elide("ne", &text, aspirate_h))
+}
+
+/// Build the affirmative and negative imperative of a verb with object pronouns
+pub fn imperative(
+    verb: &Verb,
+    templates: &HashMap<String, Template>,
+    query: &ImperativeQuery,
+) -> Result<Imperative, String> {
+    let forms = templates
+        .get(&verb.template)
+        .and_then(|template| conjugation::simple_forms(verb, template))
+        .ok_or_else(|| format!("{}: unknown template {}", verb.verb, verb.template))?;
+    let cells = Person::IMPERATIVE
+        .iter()
+        .zip(&forms[&Tense::ImperativePresent])
+        .map(|(&person, cell)| {
+            let clitics = clitics(query, person)?;
+            let missing = if properties::is_impersonal(&verb.verb) {
+                Some(MissingReason::Impersonal)
+            } else if cell.is_empty() {
+                Some(MissingReason::Defective)
+            } else {
+                None
+            };
+            if missing.is_some() {
+                return Ok(ImperativeCell {
+                    person,
+                    affirmative: None,
+                    negative: None,
+                    missing,
+                });
+            }
+            Ok(ImperativeCell {
+                person,
+                affirmative: Some(
+                    cell.iter()
+                        .map(|form| affirmative(form, person, &clitics))
+                        .collect(),
+                ),
+                negative: Some(
+                    cell.iter()
+                        .map(|form| negative(form, &clitics, verb.aspirate_h))
+                        .collect(),
+                ),
+                missing: None,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(Imperative {
+        verb: verb.verb.clone(),
+        pronominal: query.pronominal,
+        cells,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::load_all_templates;
+    use crate::verbs::load_all_verbs;
+
+    const VERBS_XML: &str = include_str!("../../data/verbs-fr.xml");
+    const CONJUGATION_XML: &str = include_str!("../../data/conjugation-fr.xml");
+
+    fn query(
+        direct: Option<DirectObject>,
+        indirect: Option<IndirectObject>,
+        y: bool,
+        en: bool,
+    ) -> ImperativeQuery {
+        ImperativeQuery {
+            direct,
+            indirect,
+            y,
+            en,
+            pronominal: false,
+        }
+    }
+
+    #[test]
+    fn clitic_order() {
+        use DirectObject as D;
+        use IndirectObject as I;
+        let cases = [
+            (
+                "donne",
+                query(Some(D::Le), Some(I::Me), false, false),
+                "donne-le-moi",
+                "ne me le donne pas",
+            ),
+            (
+                "donne",
+                query(Some(D::La), Some(I::Lui), false, false),
+                "donne-la-lui",
+                "ne la lui donne pas",
+            ),
+            (
+                "donnez",
+                query(Some(D::Les), Some(I::Leur), false, false),
+                "donnez-les-leur",
+                "ne les leur donnez pas",
+            ),
+            (
+                "donne",
+                query(None, Some(I::Me), false, true),
+                "donne-m'en",
+                "ne m'en donne pas",
+            ),
+            (
+                "mets",
+                query(Some(D::Le), None, true, false),
+                "mets-l'y",
+                "ne l'y mets pas",
+            ),
+            (
+                "donnons",
+                query(None, Some(I::Leur), false, true),
+                "donnons-leur-en",
+                "ne leur en donnons pas",
+            ),
+            (
+                "regarde",
+                query(Some(D::Nous), None, false, false),
+                "regarde-nous",
+                "ne nous regarde pas",
+            ),
+        ];
+        for (form, query, affirmative_form, negative_form) in cases {
+            let person = Person::SecondSingular;
+            let clitics = clitics(&query, person).unwrap();
+            assert_eq!(affirmative(form, person, &clitics), affirmative_form);
+            assert_eq!(negative(form, &clitics, false), negative_form);
+        }
+    }
+
+    #[test]
+    fn euphonic_s() {
+        let cases = [
+            (
+                "va",
+                Person::SecondSingular,
+                query(None, None, true, false),
+                "vas-y",
+            ),
+            (
+                "mange",
+                Person::SecondSingular,
+                query(None, None, false, true),
+                "manges-en",
+            ),
+            (
+                "prends",
+                Person::SecondSingular,
+                query(None, None, false, true),
+                "prends-en",
+            ),
+            (
+                "mangeons",
+                Person::FirstPlural,
+                query(None, None, false, true),
+                "mangeons-en",
+            ),
+            // The s is only added when y or en directly follows the verb
+            (
+                "mange",
+                Person::SecondSingular,
+                query(Some(DirectObject::Le), None, true, false),
+                "mange-l'y",
+            ),
+        ];
+        for (form, person, query, expected) in cases {
+            let clitics = clitics(&query, person).unwrap();
+            assert_eq!(affirmative(form, person, &clitics), expected);
+        }
+    }
+
+    #[test]
+    fn incompatible_pronouns_are_refused() {
+        let reflexive_and_me = ImperativeQuery {
+            indirect: Some(IndirectObject::Me),
+            pronominal: true,
+            ..ImperativeQuery::default()
+        };
+        assert!(clitics(&reflexive_and_me, Person::SecondSingular).is_err());
+        let me_and_lui = query(
+            Some(DirectObject::Me),
+            Some(IndirectObject::Lui),
+            false,
+            false,
+        );
+        assert!(clitics(&me_and_lui, Person::SecondSingular).is_err());
+    }
+
+    #[test]
+    fn pronominal_imperative() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let lever = verbs.iter().find(|verb| verb.verb == "lever").unwrap();
+        let query = ImperativeQuery {
+            pronominal: true,
+            ..ImperativeQuery::default()
+        };
+        let imperative = imperative(lever, &templates, &query).unwrap();
+        let forms: Vec<(&str, &str)> = imperative
+            .cells
+            .iter()
+            .map(|cell| {
+                (
+                    cell.affirmative.as_ref().unwrap()[0].as_str(),
+                    cell.negative.as_ref().unwrap()[0].as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            forms,
+            [
+                ("lève-toi", "ne te lève pas"),
+                ("levons-nous", "ne nous levons pas"),
+                ("levez-vous", "ne vous levez pas"),
+            ]
+        );
+    }
+}
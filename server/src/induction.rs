@@ -0,0 +1,310 @@
+use crate::conjugation::{self, Tense};
+use crate::template::{self, Template};
+use crate::verbs::{self, Verb};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Outcome of inducing a template from a full paradigm
+#[derive(Debug, Clone, Serialize)]
+pub struct Induction {
+    pub infinitive: String,
+    /// Longest common prefix of all supplied forms
+    pub stem: String,
+    /// Existing templates generating exactly the supplied forms
+    pub matches: Vec<String>,
+    /// Proposed template when no existing one matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<Template>,
+    /// `<template>` block for conjugation-fr.xml, for the proposed template
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub xml: Option<String>,
+}
+
+/// Forms of one cell without the empty strings marking missing forms
+fn forms(cell: &[String]) -> Vec<String> {
+    cell.iter()
+        .filter(|form| !form.is_empty())
+        .cloned()
+        .collect()
+}
+
+/// Longest common prefix of a set of words, on character boundaries
+fn common_prefix<'a>(words: &[&'a str]) -> &'a str {
+    let Some(first) = words.first() else {
+        return "";
+    };
+    let mut len = first.len();
+    for word in &words[1..] {
+        len = first
+            .char_indices()
+            .zip(word.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map(|((idx, c), _)| idx + c.len_utf8())
+            .unwrap_or(0)
+            .min(len);
+    }
+    &first[..len]
+}
+
+/// Whether conjugating `infinitive` with `template` yields exactly the paradigm
+fn generates(infinitive: &str, template: &Template, paradigm: &Template) -> bool {
+    let verb = Verb {
+        verb: infinitive.to_string(),
+        template: template.name.clone(),
+        aspirate_h: false,
+    };
+    let Some(generated) = conjugation::simple_forms(&verb, template) else {
+        return false;
+    };
+    Tense::SIMPLE.iter().all(|tense| {
+        let expected = conjugation::endings(paradigm, *tense);
+        let cells = &generated[tense];
+        expected.len() == cells.len()
+            && expected
+                .iter()
+                .zip(cells)
+                .all(|(expected, cell)| &forms(expected) == cell)
+    })
+}
+
+/// Split a full paradigm (a template-shaped value holding complete forms instead of
+/// endings) into stem and endings, and look for existing templates producing it
+pub fn induce(
+    paradigm: &Template,
+    templates: &HashMap<String, Template>,
+) -> Result<Induction, String> {
+    let infinitive = paradigm
+        .infinitive
+        .infinitive_present
+        .iter()
+        .find(|form| !form.is_empty())
+        .ok_or("the paradigm has no infinitive")?
+        .clone();
+
+    let all_forms: Vec<String> = Tense::SIMPLE
+        .iter()
+        .flat_map(|&tense| conjugation::endings(paradigm, tense))
+        .flatten()
+        .filter(|form| !form.is_empty())
+        .collect();
+    let all_forms: Vec<&str> = all_forms.iter().map(String::as_str).collect();
+    let stem = common_prefix(&all_forms).to_string();
+
+    let mut matches: Vec<String> = templates
+        .values()
+        .filter(|template| generates(&infinitive, template, paradigm))
+        .map(|template| template.name.clone())
+        .collect();
+    matches.sort();
+
+    if !matches.is_empty() {
+        return Ok(Induction {
+            infinitive,
+            stem,
+            matches,
+            template: None,
+            xml: None,
+        });
+    }
+
+    let strip = |cell: &Vec<String>| -> Vec<String> {
+        forms(cell)
+            .iter()
+            .map(|form| form[stem.len()..].to_string())
+            .collect()
+    };
+    let strip_all = |cells: &Vec<Vec<String>>| cells.iter().map(strip).collect();
+
+    let proposed = Template {
+        name: format!("{}:{}", stem, &infinitive[stem.len()..]),
+        infinitive: template::Infinitive {
+            infinitive_present: strip(&paradigm.infinitive.infinitive_present),
+        },
+        indicative: template::Indicative {
+            present: strip_all(&paradigm.indicative.present),
+            imperfect: strip_all(&paradigm.indicative.imperfect),
+            future: strip_all(&paradigm.indicative.future),
+            simple_past: strip_all(&paradigm.indicative.simple_past),
+        },
+        conditional: template::Conditional {
+            present: strip_all(&paradigm.conditional.present),
+        },
+        subjunctive: template::Subjunctive {
+            present: strip_all(&paradigm.subjunctive.present),
+            imperfect: strip_all(&paradigm.subjunctive.imperfect),
+        },
+        imperative: template::Imperative {
+            imperative_present: strip_all(&paradigm.imperative.imperative_present),
+        },
+        participle: template::Participle {
+            present_participle: strip(&paradigm.participle.present_participle),
+            past_participle: strip_all(&paradigm.participle.past_participle),
+        },
+    };
+    let xml = template::write_template(&proposed);
+
+    Ok(Induction {
+        infinitive,
+        stem,
+        matches,
+        template: Some(proposed),
+        xml: Some(xml),
+    })
+}
+
+/// Data files with the induced verb added, conjugated by the first matching
+/// template or else by the proposed one: verbs-fr.xml, and conjugation-fr.xml
+/// with the proposed template appended when there is one
+pub fn add_to_data(
+    induction: &Induction,
+    verbs_xml: &str,
+    conjugation_xml: &str,
+) -> Result<(String, Option<String>), Box<dyn std::error::Error>> {
+    let mut verbs = verbs::load_all_verbs(verbs_xml)?;
+    if verbs.iter().any(|verb| verb.verb == induction.infinitive) {
+        return Err(format!("{} is already listed", induction.infinitive).into());
+    }
+    let (template, conjugation_xml) = match (induction.matches.first(), &induction.template) {
+        (Some(name), _) => (name.clone(), None),
+        (None, Some(proposed)) => {
+            let templates = template::load_all_templates(conjugation_xml)?;
+            if templates.contains_key(&proposed.name) {
+                return Err(format!("template {} already exists", proposed.name).into());
+            }
+            // Templates are grouped by hand in the file: the new one goes last
+            let end = conjugation_xml
+                .rfind("</conjugation-fr>")
+                .ok_or("conjugation-fr.xml has no closing tag")?;
+            let block = induction.xml.clone().unwrap_or_default();
+            let xml = format!(
+                "{}{block}\n{}",
+                &conjugation_xml[..end],
+                &conjugation_xml[end..]
+            );
+            (proposed.name.clone(), Some(xml))
+        }
+        (None, None) => return Err("no template conjugates the paradigm".into()),
+    };
+    verbs.push(Verb {
+        verb: induction.infinitive.clone(),
+        template,
+        aspirate_h: false,
+    });
+    Ok((verbs::write_all_verbs(&verbs), conjugation_xml))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::load_all_templates;
+
+    const VERBS_XML: &str = include_str!("../../data/verbs-fr.xml");
+    const CONJUGATION_XML: &str = include_str!("../../data/conjugation-fr.xml");
+
+    /// Full paradigm of a verb: the template with the stem prepended to every ending
+    fn paradigm(template: &Template, stem: &str) -> Template {
+        let cell = |cell: &Vec<String>| -> Vec<String> {
+            cell.iter()
+                .map(|ending| {
+                    if ending.is_empty() {
+                        String::new()
+                    } else {
+                        format!("{stem}{ending}")
+                    }
+                })
+                .collect()
+        };
+        let cells = |cells: &Vec<Vec<String>>| cells.iter().map(cell).collect();
+        Template {
+            name: String::new(),
+            infinitive: template::Infinitive {
+                infinitive_present: cell(&template.infinitive.infinitive_present),
+            },
+            indicative: template::Indicative {
+                present: cells(&template.indicative.present),
+                imperfect: cells(&template.indicative.imperfect),
+                future: cells(&template.indicative.future),
+                simple_past: cells(&template.indicative.simple_past),
+            },
+            conditional: template::Conditional {
+                present: cells(&template.conditional.present),
+            },
+            subjunctive: template::Subjunctive {
+                present: cells(&template.subjunctive.present),
+                imperfect: cells(&template.subjunctive.imperfect),
+            },
+            imperative: template::Imperative {
+                imperative_present: cells(&template.imperative.imperative_present),
+            },
+            participle: template::Participle {
+                present_participle: cell(&template.participle.present_participle),
+                past_participle: cells(&template.participle.past_participle),
+            },
+        }
+    }
+
+    #[test]
+    fn paradigm_of_a_template_induces_it() {
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let induction = induce(&paradigm(&templates["fin:ir"], "fin"), &templates).unwrap();
+        assert_eq!(induction.infinitive, "finir");
+        assert_eq!(induction.stem, "fin");
+        assert!(induction.matches.contains(&"fin:ir".to_string()));
+        assert!(induction.template.is_none());
+        assert!(induction.xml.is_none());
+    }
+
+    #[test]
+    fn unknown_paradigm_proposes_a_template_generating_it() {
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let mut forms = paradigm(&templates["fin:ir"], "blor");
+        forms.indicative.present[0] = vec!["blorix".to_string()];
+
+        let induction = induce(&forms, &templates).unwrap();
+        assert!(induction.matches.is_empty());
+        let proposed = induction.template.unwrap();
+        assert_eq!(proposed.name, "blor:ir");
+        assert_eq!(proposed.indicative.present[0], ["ix"]);
+        assert!(generates("blorir", &proposed, &forms));
+        assert!(
+            induction
+                .xml
+                .unwrap()
+                .contains("<template name=\"blor:ir\">")
+        );
+    }
+
+    #[test]
+    fn paradigm_without_infinitive_is_refused() {
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let mut forms = paradigm(&templates["fin:ir"], "fin");
+        forms.infinitive.infinitive_present = Vec::new();
+        assert!(induce(&forms, &templates).is_err());
+    }
+
+    #[test]
+    fn induced_verbs_are_added_to_the_data() {
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let known = induce(&paradigm(&templates["fin:ir"], "blor"), &templates).unwrap();
+        let (verbs_xml, conjugation_xml) = add_to_data(&known, VERBS_XML, CONJUGATION_XML).unwrap();
+        assert!(conjugation_xml.is_none());
+        let verbs = verbs::load_all_verbs(&verbs_xml).unwrap();
+        let added = verbs.iter().find(|verb| verb.verb == "blorir").unwrap();
+        assert_eq!(added.template, "fin:ir");
+
+        let mut forms = paradigm(&templates["fin:ir"], "blor");
+        forms.indicative.present[0] = vec!["blorix".to_string()];
+        let proposed = induce(&forms, &templates).unwrap();
+        let (verbs_xml, conjugation_xml) =
+            add_to_data(&proposed, VERBS_XML, CONJUGATION_XML).unwrap();
+        let verbs = verbs::load_all_verbs(&verbs_xml).unwrap();
+        let added = verbs.iter().find(|verb| verb.verb == "blorir").unwrap();
+        assert_eq!(added.template, "blor:ir");
+        let templates = load_all_templates(&conjugation_xml.unwrap()).unwrap();
+        assert_eq!(templates["blor:ir"].indicative.present[0], ["ix"]);
+
+        let finir = induce(&paradigm(&templates["fin:ir"], "fin"), &templates).unwrap();
+        assert!(add_to_data(&finir, VERBS_XML, CONJUGATION_XML).is_err());
+    }
+}
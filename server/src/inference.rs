@@ -0,0 +1,244 @@
+use crate::conjugation::template_suffix;
+use crate::template::Template;
+use crate::verbs::Verb;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Longest verb ending (in characters) collected for statistics
+const MAX_ENDING_LEN: usize = 6;
+
+/// Minimum number of known verbs sharing an ending before it is trusted
+const MIN_SUPPORT: usize = 2;
+
+/// Number of analogous known verbs returned with a guess
+const MAX_ANALOGUES: usize = 5;
+
+/// Explanation attached to a verb whose template was guessed rather than looked up
+#[derive(Debug, Clone, Serialize)]
+pub struct Inference {
+    /// Share of known verbs with the same ending that use the guessed template,
+    /// smoothed with one pseudo-count so that thin evidence lowers the confidence
+    pub confidence: f64,
+    /// Ending the guess is based on
+    pub ending: String,
+    /// Known verbs sharing the ending and the guessed template
+    pub analogues: Vec<String>,
+}
+
+/// Known verbs using a given template for one ending
+#[derive(Debug, Default)]
+struct EndingStats {
+    count: usize,
+    examples: Vec<String>,
+}
+
+/// Guesses the conjugation template of infinitives missing from the dictionary,
+/// based on the templates used by known verbs with the same ending
+#[derive(Debug)]
+pub struct TemplateGuesser {
+    /// Template names that have a non-empty termination
+    templates: Vec<String>,
+    /// ending -> template name -> statistics
+    endings: HashMap<String, HashMap<String, EndingStats>>,
+}
+
+/// Last `len` characters of a word
+fn last_chars(word: &str, len: usize) -> &str {
+    let start = word
+        .char_indices()
+        .rev()
+        .nth(len - 1)
+        .map(|(idx, _)| idx)
+        .unwrap_or(0);
+    &word[start..]
+}
+
+impl TemplateGuesser {
+    /// Learn ending statistics from the known verbs
+    pub fn new(verbs: &[Verb], templates: &HashMap<String, Template>) -> Self {
+        let mut endings: HashMap<String, HashMap<String, EndingStats>> = HashMap::new();
+
+        for verb in verbs {
+            let len = verb.verb.chars().count();
+            for ending_len in 1..=MAX_ENDING_LEN.min(len) {
+                let stats = endings
+                    .entry(last_chars(&verb.verb, ending_len).to_string())
+                    .or_default()
+                    .entry(verb.template.clone())
+                    .or_default();
+                stats.count += 1;
+                if stats.examples.len() < MAX_ANALOGUES {
+                    stats.examples.push(verb.verb.clone());
+                }
+            }
+        }
+
+        let mut templates: Vec<String> = templates
+            .keys()
+            .filter(|name| !template_suffix(name).is_empty())
+            .cloned()
+            .collect();
+        templates.sort();
+
+        Self { templates, endings }
+    }
+
+    /// Guess the template of an unknown infinitive.
+    /// Only templates whose termination matches the infinitive are considered; among them
+    /// the one used by most known verbs sharing the longest common ending wins.
+    pub fn guess(&self, infinitive: &str) -> Option<(Verb, Inference)> {
+        let candidates: Vec<&String> = self
+            .templates
+            .iter()
+            .filter(|name| {
+                let suffix = template_suffix(name);
+                infinitive.len() > suffix.len() && infinitive.ends_with(suffix)
+            })
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+
+        let len = infinitive.chars().count();
+        for ending_len in (1..=MAX_ENDING_LEN.min(len)).rev() {
+            let ending = last_chars(infinitive, ending_len);
+            let Some(stats) = self.endings.get(ending) else {
+                continue;
+            };
+
+            let matching: Vec<(&String, &EndingStats)> = candidates
+                .iter()
+                .filter_map(|name| stats.get(*name).map(|s| (*name, s)))
+                .collect();
+            let total: usize = matching.iter().map(|(_, s)| s.count).sum();
+            if total < MIN_SUPPORT {
+                continue;
+            }
+
+            let (template, best) = matching
+                .iter()
+                .max_by(|a, b| a.1.count.cmp(&b.1.count).then_with(|| b.0.cmp(a.0)))?;
+
+            return Some((
+                Verb {
+                    verb: infinitive.to_string(),
+                    template: (*template).clone(),
+                    aspirate_h: false,
+                },
+                Inference {
+                    confidence: best.count as f64 / (total + 1) as f64,
+                    ending: ending.to_string(),
+                    analogues: best.examples.clone(),
+                },
+            ));
+        }
+
+        // No known verb shares an ending with a usable template: fall back to the
+        // template with the longest matching termination
+        let template = candidates
+            .iter()
+            .max_by_key(|name| template_suffix(name).len())?;
+        Some((
+            Verb {
+                verb: infinitive.to_string(),
+                template: (*template).clone(),
+                aspirate_h: false,
+            },
+            Inference {
+                confidence: 0.0,
+                ending: template_suffix(template).to_string(),
+                analogues: Vec::new(),
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::load_all_templates;
+    use crate::verbs::load_all_verbs;
+
+    const VERBS_XML: &str = include_str!("../../data/verbs-fr.xml");
+    const CONJUGATION_XML: &str = include_str!("../../data/conjugation-fr.xml");
+
+    fn verb(infinitive: &str, template: &str) -> Verb {
+        Verb {
+            verb: infinitive.to_string(),
+            template: template.to_string(),
+            aspirate_h: false,
+        }
+    }
+
+    #[test]
+    fn unknown_er_verb_follows_aimer() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let guesser = TemplateGuesser::new(&verbs, &templates);
+
+        let (verb, inference) = guesser.guess("googler").unwrap();
+        assert_eq!(verb.template, "aim:er");
+        assert!("googler".ends_with(&inference.ending));
+        assert!(!inference.analogues.is_empty());
+        for analogue in &inference.analogues {
+            assert!(analogue.ends_with(&inference.ending), "{analogue}");
+            let known = verbs.iter().find(|v| &v.verb == analogue).unwrap();
+            assert_eq!(known.template, "aim:er");
+        }
+        assert!(inference.confidence > 0.5 && inference.confidence < 1.0);
+    }
+
+    #[test]
+    fn longest_supported_ending_wins() {
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let verbs = [
+            verb("aimer", "aim:er"),
+            verb("chanter", "aim:er"),
+            verb("parler", "aim:er"),
+            verb("appeler", "app:eler"),
+            verb("rappeler", "app:eler"),
+        ];
+        let guesser = TemplateGuesser::new(&verbs, &templates);
+
+        // "-ppeler" is shared by two app:eler verbs and beats the more common "-er"
+        let (verb, inference) = guesser.guess("dappeler").unwrap();
+        assert_eq!(verb.template, "app:eler");
+        assert_eq!(inference.ending, "ppeler");
+        assert_eq!(inference.analogues, ["appeler", "rappeler"]);
+        // Two supporting verbs out of two, smoothed with one pseudo-count
+        assert!((inference.confidence - 2.0 / 3.0).abs() < 1e-9);
+
+        // Only "parler" shares "-ler" with a template ending "charler": not
+        // enough support, so the guess falls back to "-er"
+        let (verb, inference) = guesser.guess("charler").unwrap();
+        assert_eq!(verb.template, "aim:er");
+        assert_eq!(inference.ending, "er");
+    }
+
+    #[test]
+    fn equal_counts_pick_the_first_template_name() {
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let verbs = [
+            verb("ester", "est:er"),
+            verb("rester", "est:er"),
+            verb("aimer", "aim:er"),
+            verb("chanter", "aim:er"),
+        ];
+        let guesser = TemplateGuesser::new(&verbs, &templates);
+        let (verb, inference) = guesser.guess("zzer").unwrap();
+        assert_eq!(verb.template, "aim:er");
+        assert_eq!(inference.ending, "er");
+        assert!((inference.confidence - 2.0 / 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn without_evidence_the_longest_termination_is_used() {
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let guesser = TemplateGuesser::new(&[], &templates);
+        let (verb, inference) = guesser.guess("zzeler").unwrap();
+        assert!(verb.template.ends_with(":eler"), "{}", verb.template);
+        assert_eq!(inference.confidence, 0.0);
+        assert!(inference.analogues.is_empty());
+        assert!(guesser.guess("xyz").is_none());
+    }
+}
@@ -1,14 +1,40 @@
 // SPDX-License-Identifier: GPL-2.0-only
 
 #![deny(warnings)]
+mod agreement;
+mod analysis;
+mod anki;
+mod cli;
+mod conjugation;
+mod dataset;
+mod derivation;
+mod dictionary;
+mod drill;
+mod export;
 mod handlers;
+mod hunspell;
+mod imperative;
+mod induction;
+mod inference;
+mod morphology;
+mod pdf;
+mod pronouns;
+mod properties;
+mod render;
+mod review;
+mod sentence;
+mod spelling;
 mod template;
+#[cfg(test)]
+mod test_data;
 mod verbs;
+mod worksheet;
 
 use clap::Parser;
 use log::{error, info};
 use rust_embed::RustEmbed;
 use std::net::IpAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use warp::Filter;
 
@@ -24,6 +50,9 @@ struct Asset;
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<cli::Command>,
+
     /// Host address to bind to
     #[arg(long, default_value = "0.0.0.0")]
     host: String,
@@ -31,6 +60,10 @@ struct Args {
     /// Port to bind to
     #[arg(long, default_value_t = 3030)]
     port: u16,
+
+    /// SQLite database storing the review progress of learners
+    #[arg(long, default_value = "progress.sqlite")]
+    database: PathBuf,
 }
 
 #[tokio::main]
@@ -69,25 +102,284 @@ async fn main() {
         }
     };
 
-    // Configure CORS to allow all origins and GET method
+    // Run a one-off command instead of the server when one is given
+    if let Some(command) = args.command {
+        if let Err(e) = cli::run(command, &verbs, &templates) {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Learn template statistics for verbs missing from the dictionary
+    info!("Building template guesser...");
+    let guesser = Arc::new(inference::TemplateGuesser::new(&verbs, &templates));
+
+    // Open the learner progress database, creating it on first run
+    info!("Opening progress database {}...", args.database.display());
+    let store = match review::ProgressStore::open(&args.database) {
+        Ok(store) => Arc::new(store),
+        Err(e) => {
+            error!("Failed to open progress database: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Configure CORS to allow all origins and GET/POST methods
     let cors = warp::cors()
         .allow_any_origin()
-        .allow_methods(vec!["GET"])
-        .allow_headers(vec!["content-type"]);
+        .allow_methods(vec!["GET", "POST"])
+        .allow_headers(vec!["content-type", "authorization"]);
 
     // Clone Arc for use in closures
-    let verbs_for_verb_handler = verbs.clone();
+    let search_index_for_verb_handler = search_index.clone();
+    let templates_for_verb_handler = templates.clone();
+    let guesser_for_verb_handler = guesser.clone();
+    let search_index_for_conjugation_handler = search_index.clone();
+    let templates_for_conjugation_handler = templates.clone();
+    let guesser_for_conjugation_handler = guesser.clone();
+    let search_index_for_agreement_handler = search_index.clone();
+    let templates_for_agreement_handler = templates.clone();
+    let guesser_for_agreement_handler = guesser.clone();
+    let search_index_for_imperative_handler = search_index.clone();
+    let templates_for_imperative_handler = templates.clone();
+    let guesser_for_imperative_handler = guesser.clone();
+    let verbs_for_drill_handler = verbs.clone();
+    let templates_for_drill_handler = templates.clone();
+    let search_index_for_check_handler = search_index.clone();
+    let templates_for_check_handler = templates.clone();
+    let guesser_for_check_handler = guesser.clone();
+    let store_for_learner_handler = store.clone();
+    let store_for_next_review_handler = store.clone();
+    let verbs_for_next_review_handler = verbs.clone();
+    let templates_for_next_review_handler = templates.clone();
+    let store_for_answer_review_handler = store.clone();
+    let search_index_for_answer_review_handler = search_index.clone();
+    let templates_for_answer_review_handler = templates.clone();
+    let guesser_for_answer_review_handler = guesser.clone();
+    let store_for_review_stats_handler = store.clone();
+    let verbs_for_export_handler = verbs.clone();
+    let templates_for_export_handler = templates.clone();
+    let verbs_for_worksheet_handler = verbs.clone();
+    let templates_for_worksheet_handler = templates.clone();
     let search_index_for_search_handler = search_index.clone();
+    let templates_for_search_handler = templates.clone();
     let templates_for_template_handler = templates.clone();
+    let templates_for_induction_handler = templates.clone();
 
     // API routes with /api prefix
     let api_verb_route = warp::path("api")
         .and(warp::path("verb"))
         .and(warp::path::param::<String>())
+        .and(warp::query::<render::FormatQuery>())
+        .and(warp::query::<spelling::SpellingQuery>())
+        .and(warp::header::optional::<String>("accept"))
         .and(warp::get())
-        .and_then(move |verb_name: String| {
-            let verbs = verbs_for_verb_handler.clone();
-            async move { handlers::get_verb_handler(verb_name, verbs).await }
+        .and_then(
+            move |verb_name: String,
+                  format: render::FormatQuery,
+                  spelling: spelling::SpellingQuery,
+                  accept: Option<String>| {
+                let search_index = search_index_for_verb_handler.clone();
+                let templates = templates_for_verb_handler.clone();
+                let guesser = guesser_for_verb_handler.clone();
+                async move {
+                    handlers::get_verb_handler(
+                        verb_name,
+                        format,
+                        spelling.spelling,
+                        accept,
+                        search_index,
+                        templates,
+                        guesser,
+                    )
+                    .await
+                }
+            },
+        );
+
+    let api_conjugation_route = warp::path("api")
+        .and(warp::path("conjugate"))
+        .and(warp::path::param::<String>())
+        .and(warp::query::<conjugation::ConjugationOptions>())
+        .and(warp::query::<render::FormatQuery>())
+        .and(warp::header::optional::<String>("accept"))
+        .and(warp::get())
+        .and_then(
+            move |verb_name: String,
+                  options: conjugation::ConjugationOptions,
+                  format: render::FormatQuery,
+                  accept: Option<String>| {
+                let search_index = search_index_for_conjugation_handler.clone();
+                let templates = templates_for_conjugation_handler.clone();
+                let guesser = guesser_for_conjugation_handler.clone();
+                async move {
+                    handlers::get_conjugation_handler(
+                        verb_name,
+                        options,
+                        format,
+                        accept,
+                        search_index,
+                        templates,
+                        guesser,
+                    )
+                    .await
+                }
+            },
+        );
+
+    let api_agreement_route = warp::path("api")
+        .and(warp::path("agreement"))
+        .and(warp::path::param::<String>())
+        .and(warp::query::<agreement::AgreementQuery>())
+        .and(warp::get())
+        .and_then(move |verb_name: String, query: agreement::AgreementQuery| {
+            let search_index = search_index_for_agreement_handler.clone();
+            let templates = templates_for_agreement_handler.clone();
+            let guesser = guesser_for_agreement_handler.clone();
+            async move {
+                handlers::get_agreement_handler(verb_name, query, search_index, templates, guesser)
+                    .await
+            }
+        });
+
+    let api_imperative_route = warp::path("api")
+        .and(warp::path("imperative"))
+        .and(warp::path::param::<String>())
+        .and(warp::query::<imperative::ImperativeQuery>())
+        .and(warp::get())
+        .and_then(
+            move |verb_name: String, query: imperative::ImperativeQuery| {
+                let search_index = search_index_for_imperative_handler.clone();
+                let templates = templates_for_imperative_handler.clone();
+                let guesser = guesser_for_imperative_handler.clone();
+                async move {
+                    handlers::get_imperative_handler(
+                        verb_name,
+                        query,
+                        search_index,
+                        templates,
+                        guesser,
+                    )
+                    .await
+                }
+            },
+        );
+
+    let api_drill_route = warp::path("api")
+        .and(warp::path("drill"))
+        .and(warp::path::end())
+        .and(warp::query::<drill::DrillQuery>())
+        .and(warp::get())
+        .and_then(move |query: drill::DrillQuery| {
+            let verbs = verbs_for_drill_handler.clone();
+            let templates = templates_for_drill_handler.clone();
+            async move { handlers::get_drill_handler(query, verbs, templates).await }
+        });
+
+    let api_drill_check_route =
+        warp::path("api")
+            .and(warp::path("drill"))
+            .and(warp::path("check"))
+            .and(warp::path::end())
+            .and(warp::post())
+            .and(warp::body::json())
+            .and_then(move |answer: drill::DrillAnswer| {
+                let search_index = search_index_for_check_handler.clone();
+                let templates = templates_for_check_handler.clone();
+                let guesser = guesser_for_check_handler.clone();
+                async move {
+                    handlers::check_drill_handler(answer, search_index, templates, guesser).await
+                }
+            });
+
+    let api_learner_route = warp::path("api")
+        .and(warp::path("review"))
+        .and(warp::path("learners"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and_then(move || {
+            let store = store_for_learner_handler.clone();
+            async move { handlers::create_learner_handler(store).await }
+        });
+
+    let api_next_review_route = warp::path("api")
+        .and(warp::path("review"))
+        .and(warp::path("next"))
+        .and(warp::path::end())
+        .and(warp::query::<drill::DrillQuery>())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::get())
+        .and_then(
+            move |query: drill::DrillQuery, authorization: Option<String>| {
+                let store = store_for_next_review_handler.clone();
+                let verbs = verbs_for_next_review_handler.clone();
+                let templates = templates_for_next_review_handler.clone();
+                async move {
+                    handlers::next_review_handler(query, authorization, store, verbs, templates)
+                        .await
+                }
+            },
+        );
+
+    let api_answer_review_route = warp::path("api")
+        .and(warp::path("review"))
+        .and(warp::path("answer"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(
+            move |answer: drill::DrillAnswer, authorization: Option<String>| {
+                let store = store_for_answer_review_handler.clone();
+                let search_index = search_index_for_answer_review_handler.clone();
+                let templates = templates_for_answer_review_handler.clone();
+                let guesser = guesser_for_answer_review_handler.clone();
+                async move {
+                    handlers::answer_review_handler(
+                        answer,
+                        authorization,
+                        store,
+                        search_index,
+                        templates,
+                        guesser,
+                    )
+                    .await
+                }
+            },
+        );
+
+    let api_review_stats_route = warp::path("api")
+        .and(warp::path("review"))
+        .and(warp::path("stats"))
+        .and(warp::path::end())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::get())
+        .and_then(move |authorization: Option<String>| {
+            let store = store_for_review_stats_handler.clone();
+            async move { handlers::review_stats_handler(authorization, store).await }
+        });
+
+    let api_worksheet_route = warp::path("api")
+        .and(warp::path("worksheet"))
+        .and(warp::path::end())
+        .and(warp::query::<worksheet::WorksheetQuery>())
+        .and(warp::get())
+        .and_then(move |query: worksheet::WorksheetQuery| {
+            let verbs = verbs_for_worksheet_handler.clone();
+            let templates = templates_for_worksheet_handler.clone();
+            async move { handlers::get_worksheet_handler(query, verbs, templates).await }
+        });
+
+    let api_export_route = warp::path("api")
+        .and(warp::path("export"))
+        .and(warp::path::end())
+        .and(warp::query::<export::ExportQuery>())
+        .and(warp::get())
+        .and_then(move |query: export::ExportQuery| {
+            let verbs = verbs_for_export_handler.clone();
+            let templates = templates_for_export_handler.clone();
+            async move { handlers::export_handler(query, verbs, templates).await }
         });
 
     let api_template_route = warp::path("api")
@@ -102,17 +394,56 @@ async fn main() {
     let api_search_route = warp::path("api")
         .and(warp::path("search"))
         .and(warp::query::<std::collections::HashMap<String, String>>())
+        .and(warp::query::<properties::PropertyFilter>())
+        .and(warp::query::<spelling::SpellingQuery>())
         .and(warp::get())
-        .and_then(move |params: std::collections::HashMap<String, String>| {
-            let search_index = search_index_for_search_handler.clone();
-            async move {
-                let query = params.get("q").cloned().unwrap_or_default();
-                handlers::search_verbs_handler(query, search_index).await
-            }
+        .and_then(
+            move |params: std::collections::HashMap<String, String>,
+                  filter: properties::PropertyFilter,
+                  spelling: spelling::SpellingQuery| {
+                let search_index = search_index_for_search_handler.clone();
+                let templates = templates_for_search_handler.clone();
+                async move {
+                    let query = params.get("q").cloned().unwrap_or_default();
+                    handlers::search_verbs_handler(
+                        query,
+                        filter,
+                        spelling.spelling,
+                        search_index,
+                        templates,
+                    )
+                    .await
+                }
+            },
+        );
+
+    let api_induction_route = warp::path("api")
+        .and(warp::path("induce"))
+        .and(warp::path::end())
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(move |paradigm: template::Template| {
+            let templates = templates_for_induction_handler.clone();
+            async move { handlers::induce_template_handler(paradigm, templates).await }
         });
 
     // Combine API routes
-    let api_routes = api_verb_route.or(api_template_route).or(api_search_route);
+    let api_routes = api_verb_route
+        .or(api_conjugation_route)
+        .or(api_agreement_route)
+        .or(api_imperative_route)
+        .or(api_drill_route)
+        .or(api_drill_check_route)
+        .or(api_learner_route)
+        .or(api_next_review_route)
+        .or(api_answer_review_route)
+        .or(api_review_stats_route)
+        .or(api_worksheet_route)
+        .or(api_export_route)
+        .or(api_template_route)
+        .or(api_search_route)
+        .or(api_induction_route)
+        .recover(handlers::handle_api_rejection);
 
     // Serve static files from embedded public directory
     let static_files = warp::path::tail().and_then(|path: warp::path::Tail| {
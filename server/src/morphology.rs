@@ -0,0 +1,302 @@
+use crate::conjugation::{self, Gender, Number, Person, Tense};
+use crate::template::Template;
+use crate::verbs::Verb;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Morphological annotation scheme of an export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MorphologyFormat {
+    /// Tab-separated lemma, form and UniMorph features
+    #[default]
+    Unimorph,
+    /// One single-token CoNLL-U sentence per form, with Universal Dependencies features
+    Conllu,
+}
+
+/// Verb form of a cell in Universal Dependencies terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerbForm {
+    Finite,
+    Infinitive,
+    Participle,
+}
+
+/// Grammatical features of a cell of a template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Features {
+    pub verb_form: VerbForm,
+    pub tense: Tense,
+    pub person: Option<Person>,
+    pub number: Option<Number>,
+    pub gender: Option<Gender>,
+}
+
+impl Features {
+    /// Features of a cell of a simple tense; `None` for compound tenses and
+    /// cells beyond those of the tense
+    pub fn of_cell(tense: Tense, index: usize) -> Option<Features> {
+        let features =
+            |verb_form, person: Option<Person>, number: Option<Number>, gender| Features {
+                verb_form,
+                tense,
+                person,
+                number: number.or(person.map(Person::number)),
+                gender,
+            };
+        match tense {
+            Tense::InfinitivePresent if index == 0 => {
+                Some(features(VerbForm::Infinitive, None, None, None))
+            }
+            Tense::PresentParticiple if index == 0 => {
+                Some(features(VerbForm::Participle, None, None, None))
+            }
+            Tense::PastParticiple if index < 4 => {
+                let number = if index.is_multiple_of(2) {
+                    Number::Singular
+                } else {
+                    Number::Plural
+                };
+                let gender = if index < 2 {
+                    Gender::Masculine
+                } else {
+                    Gender::Feminine
+                };
+                Some(features(
+                    VerbForm::Participle,
+                    None,
+                    Some(number),
+                    Some(gender),
+                ))
+            }
+            _ if Tense::SIMPLE.contains(&tense) => {
+                let person = *tense.persons()?.get(index)?;
+                Some(features(VerbForm::Finite, Some(person), None, None))
+            }
+            _ => None,
+        }
+    }
+
+    /// UniMorph feature string: "V;IND;PRS;1;SG", "V.PTCP;PST;FEM;PL"
+    pub fn unimorph(&self) -> String {
+        let mut features: Vec<&str> = match self.tense {
+            Tense::InfinitivePresent => vec!["V", "NFIN"],
+            Tense::IndicativePresent => vec!["V", "IND", "PRS"],
+            Tense::IndicativeImperfect => vec!["V", "IND", "PST", "IPFV"],
+            Tense::IndicativeFuture => vec!["V", "IND", "FUT"],
+            Tense::IndicativeSimplePast => vec!["V", "IND", "PST", "PFV"],
+            Tense::ConditionalPresent => vec!["V", "COND"],
+            Tense::SubjunctivePresent => vec!["V", "SBJV", "PRS"],
+            Tense::SubjunctiveImperfect => vec!["V", "SBJV", "PST", "IPFV"],
+            Tense::ImperativePresent => vec!["V", "IMP"],
+            Tense::PresentParticiple => vec!["V.PTCP", "PRS"],
+            Tense::PastParticiple => vec!["V.PTCP", "PST"],
+            _ => vec!["V"],
+        };
+        if let Some(person) = self.person {
+            features.push(match person {
+                Person::FirstSingular | Person::FirstPlural => "1",
+                Person::SecondSingular | Person::SecondPlural => "2",
+                Person::ThirdSingular | Person::ThirdPlural => "3",
+            });
+        }
+        if let Some(gender) = self.gender {
+            features.push(match gender {
+                Gender::Masculine => "MASC",
+                Gender::Feminine => "FEM",
+            });
+        }
+        if let Some(number) = self.number {
+            features.push(match number {
+                Number::Singular => "SG",
+                Number::Plural => "PL",
+            });
+        }
+        features.join(";")
+    }
+
+    /// CoNLL-U FEATS column, sorted by feature name as the format requires:
+    /// "Mood=Ind|Number=Sing|Person=1|Tense=Pres|VerbForm=Fin"
+    pub fn conllu(&self) -> String {
+        let (mood, tense) = match self.tense {
+            Tense::InfinitivePresent => (None, None),
+            Tense::IndicativePresent => (Some("Ind"), Some("Pres")),
+            Tense::IndicativeImperfect => (Some("Ind"), Some("Imp")),
+            Tense::IndicativeFuture => (Some("Ind"), Some("Fut")),
+            Tense::IndicativeSimplePast => (Some("Ind"), Some("Past")),
+            Tense::ConditionalPresent => (Some("Cnd"), Some("Pres")),
+            Tense::SubjunctivePresent => (Some("Sub"), Some("Pres")),
+            Tense::SubjunctiveImperfect => (Some("Sub"), Some("Imp")),
+            Tense::ImperativePresent => (Some("Imp"), Some("Pres")),
+            Tense::PresentParticiple => (None, Some("Pres")),
+            Tense::PastParticiple => (None, Some("Past")),
+            _ => (None, None),
+        };
+        let gender = self.gender.map(|gender| match gender {
+            Gender::Masculine => "Masc",
+            Gender::Feminine => "Fem",
+        });
+        let number = self.number.map(|number| match number {
+            Number::Singular => "Sing",
+            Number::Plural => "Plur",
+        });
+        let person = self.person.map(|person| match person {
+            Person::FirstSingular | Person::FirstPlural => "1",
+            Person::SecondSingular | Person::SecondPlural => "2",
+            Person::ThirdSingular | Person::ThirdPlural => "3",
+        });
+        let verb_form = match self.verb_form {
+            VerbForm::Finite => "Fin",
+            VerbForm::Infinitive => "Inf",
+            VerbForm::Participle => "Part",
+        };
+        [
+            ("Gender", gender),
+            ("Mood", mood),
+            ("Number", number),
+            ("Person", person),
+            ("Tense", tense),
+            ("VerbForm", Some(verb_form)),
+        ]
+        .iter()
+        .filter_map(|(name, value)| value.map(|value| format!("{name}={value}")))
+        .collect::<Vec<_>>()
+        .join("|")
+    }
+}
+
+/// Every form of the simple tenses of a verb with the features of its cell,
+/// in template order; missing cells and multi-word forms are left out
+fn annotated_forms(verb: &Verb, template: &Template) -> Vec<(String, Features)> {
+    let Some(forms) = conjugation::simple_forms(verb, template) else {
+        return Vec::new();
+    };
+    let mut annotated = Vec::new();
+    for tense in Tense::SIMPLE {
+        for (index, cell) in forms[&tense].iter().enumerate() {
+            let Some(features) = Features::of_cell(tense, index) else {
+                continue;
+            };
+            for form in cell {
+                if !form.is_empty() && !form.contains(char::is_whitespace) {
+                    annotated.push((form.clone(), features));
+                }
+            }
+        }
+    }
+    annotated
+}
+
+/// Write the paradigms of the verbs in the given scheme, one verb at a time.
+/// Returns the number of forms written.
+pub fn write<'a>(
+    verbs: impl IntoIterator<Item = &'a Verb>,
+    templates: &HashMap<String, Template>,
+    format: MorphologyFormat,
+    mut writer: impl Write,
+) -> std::io::Result<usize> {
+    let mut count = 0;
+    for verb in verbs {
+        let Some(template) = templates.get(&verb.template) else {
+            continue;
+        };
+        for (form, features) in annotated_forms(verb, template) {
+            count += 1;
+            match format {
+                MorphologyFormat::Unimorph => {
+                    writeln!(writer, "{}\t{form}\t{}", verb.verb, features.unimorph())?
+                }
+                MorphologyFormat::Conllu => writeln!(
+                    writer,
+                    "# sent_id = {count}\n# text = {form}\n1\t{form}\t{}\tVERB\t_\t{}\t0\troot\t_\t_\n",
+                    verb.verb,
+                    features.conllu()
+                )?,
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_data;
+
+    #[test]
+    fn feature_strings_of_known_cells() {
+        let cases = [
+            (Tense::InfinitivePresent, 0, "V;NFIN", "VerbForm=Inf"),
+            (
+                Tense::IndicativePresent,
+                0,
+                "V;IND;PRS;1;SG",
+                "Mood=Ind|Number=Sing|Person=1|Tense=Pres|VerbForm=Fin",
+            ),
+            (
+                Tense::SubjunctiveImperfect,
+                5,
+                "V;SBJV;PST;IPFV;3;PL",
+                "Mood=Sub|Number=Plur|Person=3|Tense=Imp|VerbForm=Fin",
+            ),
+            (
+                Tense::ImperativePresent,
+                1,
+                "V;IMP;1;PL",
+                "Mood=Imp|Number=Plur|Person=1|Tense=Pres|VerbForm=Fin",
+            ),
+            (
+                Tense::PresentParticiple,
+                0,
+                "V.PTCP;PRS",
+                "Tense=Pres|VerbForm=Part",
+            ),
+            (
+                Tense::PastParticiple,
+                0,
+                "V.PTCP;PST;MASC;SG",
+                "Gender=Masc|Number=Sing|Tense=Past|VerbForm=Part",
+            ),
+            (
+                Tense::PastParticiple,
+                3,
+                "V.PTCP;PST;FEM;PL",
+                "Gender=Fem|Number=Plur|Tense=Past|VerbForm=Part",
+            ),
+        ];
+        for (tense, index, unimorph, conllu) in cases {
+            let features = Features::of_cell(tense, index).unwrap();
+            assert_eq!(features.unimorph(), unimorph, "{tense:?} {index}");
+            assert_eq!(features.conllu(), conllu, "{tense:?} {index}");
+        }
+    }
+
+    #[test]
+    fn no_features_beyond_the_cells_of_a_tense() {
+        assert!(Features::of_cell(Tense::PastParticiple, 4).is_none());
+        assert!(Features::of_cell(Tense::ImperativePresent, 3).is_none());
+        assert!(Features::of_cell(Tense::IndicativePerfect, 0).is_none());
+    }
+
+    #[test]
+    fn written_paradigms() {
+        let verbs = test_data::verbs(&["aimer"]);
+        let templates = test_data::templates();
+        let aimer = &verbs[0];
+
+        let mut output = Vec::new();
+        let count = write([aimer], &templates, MorphologyFormat::Unimorph, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), count);
+        assert!(output.contains("aimer\taimées\tV.PTCP;PST;FEM;PL\n"));
+        assert!(output.contains("aimer\taimions\tV;SBJV;PRS;1;PL\n"));
+
+        let mut output = Vec::new();
+        write([aimer], &templates, MorphologyFormat::Conllu, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with(
+            "# sent_id = 1\n# text = aimer\n1\taimer\taimer\tVERB\t_\tVerbForm=Inf\t0\troot\t_\t_\n\n"
+        ));
+    }
+}
@@ -0,0 +1,298 @@
+/// A4 page size and margins, in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 56.0;
+
+/// Advance widths of the printable ASCII characters (32 to 126) in the standard
+/// Helvetica and Helvetica-Bold fonts, in thousandths of the font size
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+/// Typographic style of a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Title,
+    Heading,
+    Body,
+    Small,
+}
+
+impl Style {
+    fn size(self) -> f32 {
+        match self {
+            Style::Title => 18.0,
+            Style::Heading => 14.0,
+            Style::Body => 12.0,
+            Style::Small => 9.0,
+        }
+    }
+
+    /// Resource name of the font in the page resources
+    fn font(self) -> &'static str {
+        match self {
+            Style::Title | Style::Heading => "F2",
+            Style::Body | Style::Small => "F1",
+        }
+    }
+
+    /// Width of a text in points. Accented letters are as wide as their base
+    /// letter; other characters outside ASCII count as a digit.
+    fn width(self, text: &str) -> f32 {
+        let widths = match self {
+            Style::Title | Style::Heading => &HELVETICA_BOLD_WIDTHS,
+            Style::Body | Style::Small => &HELVETICA_WIDTHS,
+        };
+        let units: u32 = text
+            .chars()
+            .map(|c| match c {
+                'œ' | 'Œ' => 944,
+                'æ' | 'Æ' => 889,
+                '\u{2019}' => 278,
+                _ => {
+                    let base = deunicode::deunicode_char(c)
+                        .and_then(|base| base.chars().next())
+                        .unwrap_or('0');
+                    match base {
+                        ' '..='~' => u32::from(widths[base as usize - 32]),
+                        _ => 556,
+                    }
+                }
+            })
+            .sum();
+        units as f32 * self.size() / 1000.0
+    }
+
+    /// Vertical space taken by a line, including the space above it
+    fn leading(self) -> f32 {
+        match self {
+            Style::Title => 30.0,
+            Style::Heading => 26.0,
+            Style::Body => 20.0,
+            Style::Small => 14.0,
+        }
+    }
+}
+
+/// Minimal PDF text document: A4 pages of left-aligned lines in the standard
+/// Helvetica fonts, which every PDF reader provides without embedding.
+/// Lines are laid out one below the other, breaking pages when full.
+#[derive(Debug, Default)]
+pub struct Document {
+    /// Content stream of each page
+    pages: Vec<String>,
+    /// Baseline of the next line on the last page
+    y: f32,
+}
+
+impl Document {
+    pub fn new() -> Document {
+        let mut document = Document::default();
+        document.page_break();
+        document
+    }
+
+    /// Start a new page
+    pub fn page_break(&mut self) {
+        self.pages.push(String::new());
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    /// Add a line of text, indented by the given amount of points and wrapped
+    /// at spaces to fit between the margins
+    pub fn line(&mut self, text: &str, style: Style, indent: f32) {
+        for line in wrap(text, style, PAGE_WIDTH - 2.0 * MARGIN - indent) {
+            self.single_line(&line, style, indent);
+        }
+    }
+
+    fn single_line(&mut self, text: &str, style: Style, indent: f32) {
+        if self.y - style.leading() < MARGIN {
+            self.page_break();
+        }
+        self.y -= style.leading();
+        let page = self.pages.last_mut().expect("a document has a page");
+        page.push_str(&format!(
+            "BT /{} {} Tf {:.1} {:.1} Td ({}) Tj ET\n",
+            style.font(),
+            style.size(),
+            MARGIN + indent,
+            self.y,
+            escape(text)
+        ));
+    }
+
+    /// Add vertical space
+    pub fn skip(&mut self, points: f32) {
+        self.y -= points;
+    }
+
+    /// Serialize the document
+    pub fn finish(self) -> Vec<u8> {
+        // Objects: 1 catalog, 2 page tree, 3 and 4 fonts, then a page and its
+        // content stream for each page
+        let page_count = self.pages.len();
+        let page_id = |index: usize| 5 + 2 * index;
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                (0..page_count)
+                    .map(|index| format!("{} 0 R", page_id(index)))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                page_count
+            )
+            .into_bytes(),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_vec(),
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+                .to_vec(),
+        ];
+        for (index, content) in self.pages.iter().enumerate() {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] \
+                     /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    page_id(index) + 1
+                )
+                .into_bytes(),
+            );
+            let content = win_ansi(content);
+            let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+            stream.extend_from_slice(&content);
+            stream.extend_from_slice(b"\nendstream");
+            objects.push(stream);
+        }
+
+        let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            pdf.extend_from_slice(object);
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        pdf.extend_from_slice(
+            format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+        );
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+        }
+        pdf.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref
+            )
+            .as_bytes(),
+        );
+        pdf
+    }
+}
+
+/// Split a text into lines no wider than `width`, at spaces when possible and
+/// within words longer than a line
+fn wrap(text: &str, style: Style, width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split(' ') {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{line} {word}")
+        };
+        if style.width(&candidate) <= width {
+            line = candidate;
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        for c in word.chars() {
+            line.push(c);
+            if style.width(&line) > width && line.chars().count() > 1 {
+                line.pop();
+                lines.push(std::mem::replace(&mut line, c.to_string()));
+            }
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+/// Escape the delimiters of a PDF string literal
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('(', "\\(")
+        .replace(')', "\\)")
+}
+
+/// Encode text in WinAnsiEncoding, which covers the French alphabet; other
+/// characters become "?"
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            '\u{0}'..='\u{7f}' => c as u8,
+            '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            '\u{152}' => 0x8c,
+            '\u{153}' => 0x9c,
+            '\u{178}' => 0x9f,
+            '\u{2013}' => 0x96,
+            '\u{2014}' => 0x97,
+            '\u{2018}' => 0x91,
+            '\u{2019}' => 0x92,
+            '\u{201c}' => 0x93,
+            '\u{201d}' => 0x94,
+            '\u{2026}' => 0x85,
+            _ => b'?',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_widths() {
+        // "Hello" in Helvetica: 722 + 556 + 222 + 222 + 556 thousandths
+        assert!((Style::Body.width("Hello") - 2.278 * 12.0).abs() < 1e-3);
+        assert_eq!(Style::Body.width("é"), Style::Body.width("e"));
+        assert!(Style::Title.width("aimer") > Style::Body.width("aimer"));
+    }
+
+    #[test]
+    fn long_lines_wrap_at_spaces() {
+        let width = 200.0;
+        let text = "nous aurions été aimés / nous aurions été aimées / \
+                    nous eussions été aimés / nous eussions été aimées";
+        let lines = wrap(text, Style::Body, width);
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| Style::Body.width(line) <= width));
+        assert_eq!(lines.join(" "), text);
+
+        assert_eq!(wrap("j'aime", Style::Body, width), ["j'aime"]);
+        assert_eq!(wrap("", Style::Body, width), [""]);
+    }
+
+    #[test]
+    fn words_longer_than_a_line_are_split() {
+        let lines = wrap(&"m".repeat(40), Style::Body, 100.0);
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| Style::Body.width(line) <= 100.0));
+        assert_eq!(lines.concat(), "m".repeat(40));
+    }
+}
@@ -0,0 +1,190 @@
+use crate::conjugation::{Gender, Person};
+use serde::{Deserialize, Serialize};
+
+/// Words whose final vowel is replaced by an apostrophe before a vowel sound
+const ELIDABLE: &[&str] = &["je", "me", "te", "se", "le", "la", "ne", "de", "que"];
+
+/// Vowels, including accented ones, for elision purposes
+const VOWELS: &str = "aeiouyàâäéèêëîïôöùûüœæ";
+
+/// Set of subject pronouns used to display conjugated forms
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PronounSet {
+    /// il / elle, ils / elles
+    #[default]
+    Classic,
+    /// il, ils
+    Masculine,
+    /// elle, elles
+    Feminine,
+    /// il / elle / on, ils / elles
+    WithOn,
+    /// Gender-inclusive iel, iels
+    Inclusive,
+    /// Formal "vous" addressing a single person instead of "tu"
+    Formal,
+}
+
+impl PronounSet {
+    /// Subject pronouns displayed for a person
+    pub fn subjects(self, person: Person) -> &'static [&'static str] {
+        match (self, person) {
+            (_, Person::FirstSingular) => &["je"],
+            (PronounSet::Formal, Person::SecondSingular) => &["vous"],
+            (_, Person::SecondSingular) => &["tu"],
+            (PronounSet::Masculine, Person::ThirdSingular) => &["il"],
+            (PronounSet::Feminine, Person::ThirdSingular) => &["elle"],
+            (PronounSet::WithOn, Person::ThirdSingular) => &["il", "elle", "on"],
+            (PronounSet::Inclusive, Person::ThirdSingular) => &["iel"],
+            (_, Person::ThirdSingular) => &["il", "elle"],
+            (_, Person::FirstPlural) => &["nous"],
+            (_, Person::SecondPlural) => &["vous"],
+            (PronounSet::Masculine, Person::ThirdPlural) => &["ils"],
+            (PronounSet::Feminine, Person::ThirdPlural) => &["elles"],
+            (PronounSet::Inclusive, Person::ThirdPlural) => &["iels"],
+            (_, Person::ThirdPlural) => &["ils", "elles"],
+        }
+    }
+
+    /// Person whose verb form is used for a person: formal "vous" takes the
+    /// second person plural form even when addressing one person
+    pub fn verb_person(self, person: Person) -> Person {
+        match (self, person) {
+            (PronounSet::Formal, Person::SecondSingular) => Person::SecondPlural,
+            _ => person,
+        }
+    }
+}
+
+/// Whether a word starts with a vowel sound: a vowel, or a mute h.
+/// An initial "y" followed by a vowel is a consonant ("yodler").
+pub fn starts_with_vowel_sound(word: &str, aspirate_h: bool) -> bool {
+    let mut chars = word.chars().map(|c| c.to_lowercase().next().unwrap_or(c));
+    match chars.next() {
+        Some('h') => !aspirate_h,
+        Some('y') => !chars.next().is_some_and(|c| VOWELS.contains(c)),
+        Some(c) => VOWELS.contains(c),
+        None => false,
+    }
+}
+
+/// Join a word to the next one, eliding it when needed ("je" + "aime" -> "j'aime",
+/// "que" + "il" -> "qu'il"). `aspirate_h` applies to `next`.
+pub fn elide(word: &str, next: &str, aspirate_h: bool) -> String {
+    if ELIDABLE.contains(&word) && starts_with_vowel_sound(next, aspirate_h) {
+        format!("{}'{}", &word[..word.len() - 1], next)
+    } else {
+        format!("{} {}", word, next)
+    }
+}
+
+/// Reflexive pronoun agreeing with the person of the verb form
+pub fn reflexive_pronoun(person: Person) -> &'static str {
+    match person {
+        Person::FirstSingular => "me",
+        Person::SecondSingular => "te",
+        Person::ThirdSingular | Person::ThirdPlural => "se",
+        Person::FirstPlural => "nous",
+        Person::SecondPlural => "vous",
+    }
+}
+
+/// Stressed form of the reflexive pronoun, used after an affirmative imperative
+/// ("lève-toi", "levons-nous")
+pub fn stressed_reflexive_pronoun(person: Person) -> &'static str {
+    match person {
+        Person::FirstSingular => "moi",
+        Person::SecondSingular => "toi",
+        Person::ThirdSingular | Person::ThirdPlural => "soi",
+        Person::FirstPlural => "nous",
+        Person::SecondPlural => "vous",
+    }
+}
+
+/// Grammatical gender carried by a subject pronoun, if any
+pub fn subject_gender(subject: &str) -> Option<Gender> {
+    match subject {
+        "il" | "ils" => Some(Gender::Masculine),
+        "elle" | "elles" => Some(Gender::Feminine),
+        _ => None,
+    }
+}
+
+/// Split a leading reflexive pronoun off a query ("se lever", "s'asseoir"),
+/// returning the bare infinitive and whether the verb is pronominal
+pub fn strip_reflexive(query: &str) -> (&str, bool) {
+    let trimmed = query.trim_start();
+    for prefix in ["se ", "s'", "s\u{2019}"] {
+        if let Some(rest) = trimmed.strip_prefix(prefix) {
+            let rest = rest.trim_start();
+            if !rest.is_empty() {
+                return (rest, true);
+            }
+        }
+    }
+    (query, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elision() {
+        let cases = [
+            ("je", "aime", false, "j'aime"),
+            ("je", "chante", false, "je chante"),
+            ("je", "habite", false, "j'habite"),
+            ("je", "hais", true, "je hais"),
+            ("que", "il", false, "qu'il"),
+            ("que", "elles", false, "qu'elles"),
+            ("que", "nous", false, "que nous"),
+            ("ne", "écoute", false, "n'écoute"),
+            ("se", "yodle", false, "se yodle"),
+            ("se", "y", false, "s'y"),
+            ("me", "œuvre", false, "m'œuvre"),
+            ("tu", "aimes", false, "tu aimes"),
+            ("nous", "aimons", false, "nous aimons"),
+        ];
+        for (word, next, aspirate_h, expected) in cases {
+            assert_eq!(elide(word, next, aspirate_h), expected, "{word} + {next}");
+        }
+    }
+
+    #[test]
+    fn vowel_sounds() {
+        let cases = [
+            ("aimer", false, true),
+            ("Écouter", false, true),
+            ("habiter", false, true),
+            ("haïr", true, false),
+            ("yodler", false, false),
+            ("ypériter", false, true),
+            ("parler", false, false),
+            ("", false, false),
+        ];
+        for (word, aspirate_h, expected) in cases {
+            assert_eq!(
+                starts_with_vowel_sound(word, aspirate_h),
+                expected,
+                "{word}"
+            );
+        }
+    }
+
+    #[test]
+    fn reflexive_queries() {
+        let cases = [
+            ("se lever", ("lever", true)),
+            ("s'asseoir", ("asseoir", true)),
+            ("s\u{2019}asseoir", ("asseoir", true)),
+            ("  se  laver", ("laver", true)),
+            ("se ", ("se ", false)),
+            ("sembler", ("sembler", false)),
+        ];
+        for (query, expected) in cases {
+            assert_eq!(strip_reflexive(query), expected, "{query}");
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use crate::conjugation::{self, Tense};
+use crate::template::Template;
+use crate::verbs::Verb;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Verbs only used impersonally, with the subject "il" ("il faut", "il neige")
+const IMPERSONAL_VERBS: &[&str] = &[
+    "brouillasser",
+    "bruiner",
+    "brumer",
+    "falloir",
+    "grêler",
+    "neiger",
+    "pleuvoir",
+    "pleuviner",
+    "venter",
+];
+
+/// Grammatical properties of a verb that restrict its conjugation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct VerbProperties {
+    /// Only conjugated in the third person singular
+    pub impersonal: bool,
+    /// Some forms of its template do not exist
+    pub defective: bool,
+}
+
+/// Filter on verb properties, read from the query string; unset fields match any verb
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct PropertyFilter {
+    pub impersonal: Option<bool>,
+    pub defective: Option<bool>,
+}
+
+impl PropertyFilter {
+    pub fn matches(&self, properties: &VerbProperties) -> bool {
+        self.impersonal
+            .is_none_or(|impersonal| impersonal == properties.impersonal)
+            && self
+                .defective
+                .is_none_or(|defective| defective == properties.defective)
+    }
+}
+
+pub fn is_impersonal(verb: &str) -> bool {
+    IMPERSONAL_VERBS.contains(&verb)
+}
+
+/// Whether a template has an empty cell (`<p></p>`) in some simple tense
+pub fn is_defective(template: &Template) -> bool {
+    Tense::SIMPLE.iter().any(|&tense| {
+        conjugation::endings(template, tense)
+            .iter()
+            .any(|cell| cell.is_empty())
+    })
+}
+
+pub fn properties(verb: &Verb, templates: &HashMap<String, Template>) -> VerbProperties {
+    VerbProperties {
+        impersonal: is_impersonal(&verb.verb),
+        defective: templates.get(&verb.template).is_some_and(is_defective),
+    }
+}
+
+/// Traditional conjugation group: 1 for "-er" verbs but "aller", 2 for "-ir" verbs
+/// with a present participle in "-issant" ("finir"), 3 for the others
+pub fn group(verb: &Verb, template: &Template) -> u8 {
+    if verb.verb != "aller" && verb.verb.ends_with("er") {
+        1
+    } else if conjugation::template_suffix(&template.name) == "ir"
+        && template
+            .participle
+            .present_participle
+            .iter()
+            .any(|ending| ending.ends_with("ssant"))
+    {
+        2
+    } else {
+        3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conjugation::{Cell, ConjugationOptions, MissingReason, Person};
+    use crate::template::load_all_templates;
+    use crate::verbs::load_all_verbs;
+
+    const VERBS_XML: &str = include_str!("../../data/verbs-fr.xml");
+    const CONJUGATION_XML: &str = include_str!("../../data/conjugation-fr.xml");
+
+    fn cells(verb: &str, tense: Tense) -> Vec<Cell> {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let verb = verbs.iter().find(|v| v.verb == verb).unwrap();
+        let conjugation =
+            conjugation::conjugate(verb, &templates, &ConjugationOptions::default()).unwrap();
+        conjugation
+            .tenses
+            .into_iter()
+            .find(|table| table.tense == tense)
+            .unwrap()
+            .cells
+    }
+
+    #[test]
+    fn impersonal_verbs_are_listed_verbs() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        for name in IMPERSONAL_VERBS {
+            let verb = verbs.iter().find(|v| v.verb == *name).unwrap();
+            assert!(properties(verb, &templates).impersonal, "{name}");
+        }
+        let aimer = verbs.iter().find(|v| v.verb == "aimer").unwrap();
+        assert_eq!(
+            properties(aimer, &templates),
+            VerbProperties {
+                impersonal: false,
+                defective: false,
+            }
+        );
+    }
+
+    #[test]
+    fn impersonal_verbs_only_have_the_third_person_singular() {
+        let present = cells("pleuvoir", Tense::IndicativePresent);
+        for cell in &present {
+            if cell.person == Some(Person::ThirdSingular) {
+                assert_eq!(cell.display.as_ref().unwrap()[0], "il pleut");
+                assert_eq!(cell.missing, None);
+            } else {
+                assert_eq!(cell.forms, None);
+                assert_eq!(cell.missing, Some(MissingReason::Impersonal));
+            }
+        }
+        let imperative = cells("falloir", Tense::ImperativePresent);
+        assert!(
+            imperative
+                .iter()
+                .all(|cell| cell.missing == Some(MissingReason::Impersonal))
+        );
+    }
+
+    #[test]
+    fn defective_cells() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let clore = verbs.iter().find(|v| v.verb == "clore").unwrap();
+        assert_eq!(
+            properties(clore, &templates),
+            VerbProperties {
+                impersonal: false,
+                defective: true,
+            }
+        );
+        let imperfect = cells("clore", Tense::IndicativeImperfect);
+        assert!(
+            imperfect
+                .iter()
+                .all(|cell| cell.missing == Some(MissingReason::Defective))
+        );
+        assert!(
+            cells("aimer", Tense::IndicativeImperfect)
+                .iter()
+                .all(|cell| cell.missing.is_none())
+        );
+    }
+
+    #[test]
+    fn property_filter() {
+        let impersonal = VerbProperties {
+            impersonal: true,
+            defective: true,
+        };
+        let regular = VerbProperties::default();
+        let cases = [
+            (PropertyFilter::default(), true, true),
+            (
+                PropertyFilter {
+                    impersonal: Some(true),
+                    defective: None,
+                },
+                true,
+                false,
+            ),
+            (
+                PropertyFilter {
+                    impersonal: None,
+                    defective: Some(false),
+                },
+                false,
+                true,
+            ),
+        ];
+        for (filter, matches_impersonal, matches_regular) in cases {
+            assert_eq!(filter.matches(&impersonal), matches_impersonal);
+            assert_eq!(filter.matches(&regular), matches_regular);
+        }
+    }
+}
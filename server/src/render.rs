@@ -0,0 +1,342 @@
+use crate::conjugation::{Conjugation, Mood, TenseTable};
+use serde::{Deserialize, Serialize};
+use warp::Reply;
+
+/// Representation of a verb or conjugation response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextFormat {
+    Json,
+    /// HTML fragment to embed in a page
+    Html,
+    /// Markdown tables
+    Markdown,
+    /// Plain text with aligned columns
+    Text,
+}
+
+impl TextFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            TextFormat::Json => "application/json",
+            TextFormat::Html => "text/html; charset=utf-8",
+            TextFormat::Markdown => "text/markdown; charset=utf-8",
+            TextFormat::Text => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+/// Explicit choice of representation, read from the query string
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct FormatQuery {
+    pub format: Option<TextFormat>,
+}
+
+/// Representation of a response: the `format` query parameter when given,
+/// otherwise the acceptable media type of highest quality in the `Accept`
+/// header, JSON when there is none. `None` when nothing acceptable is offered.
+pub fn negotiate(query: FormatQuery, accept: Option<&str>) -> Option<TextFormat> {
+    if let Some(format) = query.format {
+        return Some(format);
+    }
+    let Some(accept) = accept.filter(|accept| !accept.trim().is_empty()) else {
+        return Some(TextFormat::Json);
+    };
+    let mut best: Option<(f32, TextFormat)> = None;
+    for range in accept.split(',') {
+        let mut parameters = range.split(';');
+        let media_type = parameters.next().unwrap_or("").trim().to_ascii_lowercase();
+        let quality = parameters
+            .filter_map(|parameter| parameter.trim().strip_prefix("q="))
+            .find_map(|quality| quality.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        let format = match media_type.as_str() {
+            "application/json" | "application/*" | "*/*" => TextFormat::Json,
+            "text/html" | "application/xhtml+xml" => TextFormat::Html,
+            "text/markdown" => TextFormat::Markdown,
+            "text/plain" | "text/*" => TextFormat::Text,
+            _ => continue,
+        };
+        // Earlier ranges win ties, as clients list their preference first
+        if quality > 0.0 && best.is_none_or(|(best_quality, _)| quality > best_quality) {
+            best = Some((quality, format));
+        }
+    }
+    best.map(|(_, format)| format)
+}
+
+/// Escape text for HTML element content and quoted attribute values
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A captioned table of text cells, rendered in every text format
+#[derive(Debug, Clone)]
+pub struct Table {
+    pub caption: Option<String>,
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Tables of a conjugation, one per mood with a column per tense. Cells that
+/// do not exist are shown as a dash.
+pub fn conjugation_tables(conjugation: &Conjugation) -> Vec<Table> {
+    let mut moods: Vec<(Mood, Vec<&TenseTable>)> = Vec::new();
+    for table in conjugation.tenses.iter().chain(&conjugation.derived) {
+        match moods.iter_mut().find(|(mood, _)| *mood == table.mood) {
+            Some((_, tenses)) => tenses.push(table),
+            None => moods.push((table.mood, vec![table])),
+        }
+    }
+    moods
+        .into_iter()
+        .map(|(mood, tenses)| {
+            let height = tenses.iter().map(|t| t.cells.len()).max().unwrap_or(0);
+            let rows = (0..height)
+                .map(|row| {
+                    tenses
+                        .iter()
+                        .map(|tense| match tense.cells.get(row) {
+                            Some(cell) => match &cell.display {
+                                Some(display) => display.join(" / "),
+                                None => "—".to_string(),
+                            },
+                            None => String::new(),
+                        })
+                        .collect()
+                })
+                .collect();
+            Table {
+                caption: Some(mood.label()),
+                header: tenses.iter().map(|t| t.tense.label()).collect(),
+                rows,
+            }
+        })
+        .collect()
+}
+
+fn html(title: &str, tables: &[Table]) -> String {
+    let mut html = format!(
+        "<section class=\"verbiste\">\n<h2>{}</h2>\n",
+        escape_html(title)
+    );
+    for table in tables {
+        html.push_str("<table>\n");
+        if let Some(caption) = &table.caption {
+            html.push_str(&format!("<caption>{}</caption>\n", escape_html(caption)));
+        }
+        html.push_str("<thead><tr>");
+        for heading in &table.header {
+            html.push_str(&format!("<th>{}</th>", escape_html(heading)));
+        }
+        html.push_str("</tr></thead>\n<tbody>\n");
+        for row in &table.rows {
+            html.push_str("<tr>");
+            for cell in row {
+                html.push_str(&format!("<td>{}</td>", escape_html(cell)));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</tbody>\n</table>\n");
+    }
+    html.push_str("</section>\n");
+    html
+}
+
+fn markdown(title: &str, tables: &[Table]) -> String {
+    let line = |cells: &[String]| {
+        let cells: Vec<String> = cells.iter().map(|cell| cell.replace('|', "\\|")).collect();
+        format!("| {} |\n", cells.join(" | "))
+    };
+    let mut markdown = format!("## {title}\n");
+    for table in tables {
+        markdown.push('\n');
+        if let Some(caption) = &table.caption {
+            markdown.push_str(&format!("### {caption}\n\n"));
+        }
+        markdown.push_str(&line(&table.header));
+        markdown.push_str(&line(&vec!["---".to_string(); table.header.len()]));
+        for row in &table.rows {
+            markdown.push_str(&line(row));
+        }
+    }
+    markdown
+}
+
+fn text(title: &str, tables: &[Table]) -> String {
+    let mut text = format!("{title}\n{}\n", "=".repeat(title.chars().count()));
+    for table in tables {
+        text.push('\n');
+        if let Some(caption) = &table.caption {
+            text.push_str(&format!(
+                "{caption}\n{}\n",
+                "-".repeat(caption.chars().count())
+            ));
+        }
+        let mut widths = vec![0; table.header.len()];
+        for row in std::iter::once(&table.header).chain(&table.rows) {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        for row in std::iter::once(&table.header).chain(&table.rows) {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{cell:width$}"))
+                .collect();
+            text.push_str(cells.join("  ").trim_end());
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Response in the negotiated format: the value itself as JSON, or a document
+/// titled `title` made of the tables built from it. Responses vary with the
+/// `Accept` header.
+pub fn reply<T: Serialize>(
+    format: TextFormat,
+    value: &T,
+    title: &str,
+    tables: impl FnOnce(&T) -> Vec<Table>,
+) -> warp::reply::Response {
+    let mut response = match format {
+        TextFormat::Json => warp::reply::json(value).into_response(),
+        TextFormat::Html => warp::reply::Response::new(html(title, &tables(value)).into()),
+        TextFormat::Markdown => warp::reply::Response::new(markdown(title, &tables(value)).into()),
+        TextFormat::Text => warp::reply::Response::new(text(title, &tables(value)).into()),
+    };
+    let headers = response.headers_mut();
+    headers.insert(
+        "content-type",
+        warp::http::HeaderValue::from_static(format.content_type()),
+    );
+    headers.insert("vary", warp::http::HeaderValue::from_static("accept"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conjugation::{self, ConjugationOptions};
+
+    const VERBS_XML: &str = include_str!("../../data/verbs-fr.xml");
+    const CONJUGATION_XML: &str = include_str!("../../data/conjugation-fr.xml");
+
+    fn accept(header: &str) -> Option<TextFormat> {
+        negotiate(FormatQuery::default(), Some(header))
+    }
+
+    fn sample() -> Vec<Table> {
+        let cells = |cells: &[&str]| cells.iter().map(|cell| cell.to_string()).collect();
+        vec![Table {
+            caption: Some("Indicative".to_string()),
+            header: cells(&["Present", "Future"]),
+            rows: vec![cells(&["j'aime", "j'aimerai"]), cells(&["a|b <c>", "—"])],
+        }]
+    }
+
+    #[test]
+    fn negotiation() {
+        let cases = [
+            ("", Some(TextFormat::Json)),
+            ("*/*", Some(TextFormat::Json)),
+            ("text/html", Some(TextFormat::Html)),
+            ("Text/Markdown", Some(TextFormat::Markdown)),
+            ("text/*", Some(TextFormat::Text)),
+            ("text/plain;q=0.5, text/html;q=0.8", Some(TextFormat::Html)),
+            ("text/html;q=0.2, */*;q=0.5", Some(TextFormat::Json)),
+            // Ties go to the range listed first
+            ("text/markdown, text/html", Some(TextFormat::Markdown)),
+            (
+                "text/html;q=0.5, text/markdown;q=0.5",
+                Some(TextFormat::Html),
+            ),
+            ("image/png, text/plain", Some(TextFormat::Text)),
+            ("text/html;q=0", None),
+            ("image/png", None),
+        ];
+        for (header, format) in cases {
+            assert_eq!(accept(header), format, "{header:?}");
+        }
+        assert_eq!(
+            negotiate(FormatQuery::default(), None),
+            Some(TextFormat::Json)
+        );
+        // The query parameter overrides the header
+        let query = FormatQuery {
+            format: Some(TextFormat::Text),
+        };
+        assert_eq!(negotiate(query, Some("image/png")), Some(TextFormat::Text));
+    }
+
+    #[test]
+    fn html_escapes_cells() {
+        assert_eq!(
+            html("aimer & co", &sample()),
+            "<section class=\"verbiste\">\n<h2>aimer &amp; co</h2>\n<table>\n\
+             <caption>Indicative</caption>\n<thead><tr><th>Present</th><th>Future</th></tr></thead>\n\
+             <tbody>\n<tr><td>j'aime</td><td>j'aimerai</td></tr>\n\
+             <tr><td>a|b &lt;c&gt;</td><td>—</td></tr>\n</tbody>\n</table>\n</section>\n"
+        );
+    }
+
+    #[test]
+    fn markdown_escapes_pipes() {
+        assert_eq!(
+            markdown("aimer", &sample()),
+            "## aimer\n\n### Indicative\n\n| Present | Future |\n| --- | --- |\n\
+             | j'aime | j'aimerai |\n| a\\|b <c> | — |\n"
+        );
+    }
+
+    #[test]
+    fn text_aligns_columns_by_characters() {
+        assert_eq!(
+            text("aimer", &sample()),
+            "aimer\n=====\n\nIndicative\n----------\n\
+             Present  Future\n\
+             j'aime   j'aimerai\n\
+             a|b <c>  —\n"
+        );
+    }
+
+    #[test]
+    fn replies_carry_the_content_type() {
+        let response = reply(TextFormat::Markdown, &"aimer", "aimer", |_| sample());
+        let headers = response.headers();
+        assert_eq!(headers["content-type"], "text/markdown; charset=utf-8");
+        assert_eq!(headers["vary"], "accept");
+    }
+
+    #[test]
+    fn one_table_per_mood() {
+        let verbs = crate::verbs::load_all_verbs(VERBS_XML).unwrap();
+        let templates = crate::template::load_all_templates(CONJUGATION_XML).unwrap();
+        let aimer = verbs.iter().find(|v| v.verb == "aimer").unwrap();
+        let options = ConjugationOptions::default();
+        let conjugation = conjugation::conjugate(aimer, &templates, &options).unwrap();
+
+        let tables = conjugation_tables(&conjugation);
+        let captions: Vec<&str> = tables
+            .iter()
+            .filter_map(|table| table.caption.as_deref())
+            .collect();
+        assert_eq!(captions[..3], ["Infinitive", "Indicative", "Conditional"]);
+        let indicative = &tables[1];
+        assert_eq!(indicative.header[0], "Indicative present");
+        assert_eq!(indicative.rows.len(), 6);
+        assert_eq!(indicative.rows[0][0], "j'aime");
+        assert!(
+            indicative
+                .rows
+                .iter()
+                .all(|row| row.len() == indicative.header.len())
+        );
+    }
+}
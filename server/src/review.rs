@@ -0,0 +1,665 @@
+use crate::conjugation::Tense;
+use crate::drill::{self, Drill, DrillAnswer, DrillQuery, DrillResult, Mistake};
+use crate::template::Template;
+use crate::verbs::Verb;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+/// Tables of the progress database: learners identified by a token, the SM-2
+/// schedule of each verb × tense item they have seen, and every answer given
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS learners (
+    token TEXT PRIMARY KEY,
+    created INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS items (
+    learner TEXT NOT NULL REFERENCES learners (token),
+    verb TEXT NOT NULL,
+    tense TEXT NOT NULL,
+    repetitions INTEGER NOT NULL,
+    interval REAL NOT NULL,
+    ease REAL NOT NULL,
+    due INTEGER NOT NULL,
+    PRIMARY KEY (learner, verb, tense)
+);
+CREATE TABLE IF NOT EXISTS reviews (
+    id INTEGER PRIMARY KEY,
+    learner TEXT NOT NULL REFERENCES learners (token),
+    verb TEXT NOT NULL,
+    tense TEXT NOT NULL,
+    person TEXT NOT NULL,
+    answer TEXT NOT NULL,
+    correct INTEGER NOT NULL,
+    mistake TEXT,
+    quality INTEGER NOT NULL,
+    reviewed INTEGER NOT NULL
+);
+";
+
+/// Why a review request failed
+#[derive(Debug)]
+pub enum ReviewError {
+    /// Missing or unknown learner token
+    Unauthorized,
+    /// The request cannot be served (no item matches the filters, unknown cell)
+    Invalid(String),
+    /// The database failed
+    Storage(String),
+}
+
+impl From<rusqlite::Error> for ReviewError {
+    fn from(error: rusqlite::Error) -> Self {
+        ReviewError::Storage(error.to_string())
+    }
+}
+
+/// SM-2 scheduling state of one verb × tense item
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Schedule {
+    /// Consecutive successful reviews
+    repetitions: u32,
+    /// Days until the next review
+    interval: f64,
+    ease: f64,
+}
+
+impl Schedule {
+    const NEW: Schedule = Schedule {
+        repetitions: 0,
+        interval: 0.0,
+        ease: 2.5,
+    };
+
+    /// Schedule after a review graded from 0 (blackout) to 5 (perfect recall)
+    fn review(self, quality: u8) -> Schedule {
+        let q = f64::from(quality);
+        let ease = (self.ease + 0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02)).max(1.3);
+        if quality < 3 {
+            return Schedule {
+                repetitions: 0,
+                interval: 1.0,
+                ease,
+            };
+        }
+        let interval = match self.repetitions {
+            0 => 1.0,
+            1 => 6.0,
+            _ => (self.interval * self.ease).round(),
+        };
+        Schedule {
+            repetitions: self.repetitions + 1,
+            interval,
+            ease,
+        }
+    }
+}
+
+/// SM-2 grade of a drill answer: a right answer is a perfect recall, a mere
+/// accent slip still counts as recalled, grammar mistakes and wrong forms as failures
+fn quality(result: &DrillResult) -> u8 {
+    match result.mistake {
+        None => 5,
+        Some(Mistake::AccentOnly) => 3,
+        Some(Mistake::WrongAgreement | Mistake::WrongAuxiliary) => 2,
+        Some(Mistake::WrongEnding | Mistake::WrongStem) => 1,
+        Some(Mistake::Other) => 0,
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Kebab-case name a value serializes to ("first-singular", "wrong-ending")
+fn name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn parse_tense(name: &str) -> Option<Tense> {
+    Tense::ALL.into_iter().find(|tense| tense.name() == name)
+}
+
+/// Next item to review
+#[derive(Debug, Serialize)]
+pub struct ReviewItem {
+    #[serde(flatten)]
+    pub drill: Drill,
+    /// Whether the learner has never seen this verb × tense
+    pub new: bool,
+    /// When the item was due, as a Unix timestamp; `None` for new items
+    pub due: Option<i64>,
+}
+
+/// Grade of an answer and the resulting schedule of its item
+#[derive(Debug, Serialize)]
+pub struct ReviewOutcome {
+    #[serde(flatten)]
+    pub result: DrillResult,
+    /// SM-2 grade, from 0 to 5
+    pub quality: u8,
+    /// Days until the next review
+    pub interval: f64,
+    /// Unix timestamp of the next review
+    pub due: i64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TenseStats {
+    pub tense: String,
+    pub reviews: u64,
+    pub correct: u64,
+}
+
+/// Progress of a learner
+#[derive(Debug, Default, Serialize)]
+pub struct ReviewStats {
+    /// Verb × tense items seen
+    pub items: u64,
+    /// Items due for review now
+    pub due: u64,
+    /// Items recalled at least three times in a row
+    pub mature: u64,
+    pub reviews: u64,
+    pub correct: u64,
+    /// Share of correct answers, from 0 to 1
+    pub accuracy: f64,
+    /// Number of wrong answers per kind of mistake
+    pub mistakes: BTreeMap<String, u64>,
+    pub tenses: Vec<TenseStats>,
+}
+
+/// Learner progress stored in a local SQLite database
+pub struct ProgressStore {
+    connection: Mutex<Connection>,
+}
+
+impl ProgressStore {
+    /// Open the database at a path, creating it and its tables when missing
+    pub fn open(path: &Path) -> rusqlite::Result<ProgressStore> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(ProgressStore {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+        // A panic while holding the lock leaves the connection itself usable
+        self.connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Register a new learner and return its token
+    pub fn create_learner(&self) -> Result<String, ReviewError> {
+        let mut rng = StdRng::from_os_rng();
+        let token: String = (0..16)
+            .map(|_| format!("{:02x}", rng.random::<u8>()))
+            .collect();
+        self.connection().execute(
+            "INSERT INTO learners (token, created) VALUES (?1, ?2)",
+            params![token, now()],
+        )?;
+        Ok(token)
+    }
+
+    /// Check that a token belongs to a learner
+    fn authenticate(&self, token: Option<&str>) -> Result<String, ReviewError> {
+        let token = token.ok_or(ReviewError::Unauthorized)?;
+        let known = self
+            .connection()
+            .query_row(
+                "SELECT 1 FROM learners WHERE token = ?1",
+                params![token],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if known {
+            Ok(token.to_string())
+        } else {
+            Err(ReviewError::Unauthorized)
+        }
+    }
+
+    /// Items the learner has already seen, with their due date, earliest first
+    fn items(&self, learner: &str) -> Result<Vec<(String, Tense, i64)>, ReviewError> {
+        let connection = self.connection();
+        let mut statement = connection
+            .prepare("SELECT verb, tense, due FROM items WHERE learner = ?1 ORDER BY due")?;
+        let rows = statement.query_map(params![learner], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })?;
+        let mut items = Vec::new();
+        for row in rows {
+            let (verb, tense, due) = row?;
+            if let Some(tense) = parse_tense(&tense) {
+                items.push((verb, tense, due));
+            }
+        }
+        Ok(items)
+    }
+
+    fn schedule(&self, learner: &str, verb: &str, tense: Tense) -> Result<Schedule, ReviewError> {
+        let schedule = self
+            .connection()
+            .query_row(
+                "SELECT repetitions, interval, ease FROM items
+                 WHERE learner = ?1 AND verb = ?2 AND tense = ?3",
+                params![learner, verb, tense.name()],
+                |row| {
+                    Ok(Schedule {
+                        repetitions: row.get(0)?,
+                        interval: row.get(1)?,
+                        ease: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(schedule.unwrap_or(Schedule::NEW))
+    }
+
+    /// Store an answer and the new schedule of its item, returning when it is due
+    fn record(
+        &self,
+        learner: &str,
+        answer: &DrillAnswer,
+        result: &DrillResult,
+        quality: u8,
+        schedule: Schedule,
+    ) -> Result<i64, ReviewError> {
+        let reviewed = now();
+        let due = reviewed + (schedule.interval * SECONDS_PER_DAY) as i64;
+        let mut connection = self.connection();
+        let transaction = connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO reviews
+             (learner, verb, tense, person, answer, correct, mistake, quality, reviewed)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                learner,
+                answer.verb,
+                answer.tense.name(),
+                name(&answer.person),
+                result.answer,
+                result.correct,
+                result.mistake.as_ref().map(name),
+                quality,
+                reviewed,
+            ],
+        )?;
+        transaction.execute(
+            "INSERT INTO items (learner, verb, tense, repetitions, interval, ease, due)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (learner, verb, tense) DO UPDATE SET
+             repetitions = excluded.repetitions, interval = excluded.interval,
+             ease = excluded.ease, due = excluded.due",
+            params![
+                learner,
+                answer.verb,
+                answer.tense.name(),
+                schedule.repetitions,
+                schedule.interval,
+                schedule.ease,
+                due,
+            ],
+        )?;
+        transaction.commit()?;
+        Ok(due)
+    }
+}
+
+/// Next item for a learner: the most overdue item, or else a new verb × tense,
+/// or else the item coming up soonest; all of them matching the query
+pub fn next(
+    store: &ProgressStore,
+    token: Option<&str>,
+    verbs: &[Verb],
+    templates: &HashMap<String, Template>,
+    query: &DrillQuery,
+) -> Result<ReviewItem, ReviewError> {
+    let learner = store.authenticate(token)?;
+    let items = store.items(&learner)?;
+    let tenses = drill::parse_tenses(query.tenses.as_deref()).map_err(ReviewError::Invalid)?;
+    let mut rng = StdRng::from_os_rng();
+    let review = |verb: &str, tense: Tense, due: i64, rng: &mut StdRng| {
+        let index = verbs.binary_search_by(|v| v.verb.as_str().cmp(verb)).ok()?;
+        let verb = &verbs[index];
+        let wanted = tenses.contains(&tense)
+            && drill::matches(verb, templates, query.template.as_deref(), query.group);
+        if !wanted {
+            return None;
+        }
+        let mut conjugations = drill::Conjugations::new(templates);
+        let drill = drill::drill(verb, &mut conjugations, tense, rng)?;
+        Some(ReviewItem {
+            drill,
+            new: false,
+            due: Some(due),
+        })
+    };
+
+    let now = now();
+    for (verb, tense, due) in items.iter().take_while(|(_, _, due)| *due <= now) {
+        if let Some(item) = review(verb, *tense, *due, &mut rng) {
+            return Ok(item);
+        }
+    }
+    let seen: HashSet<(&str, Tense)> = items
+        .iter()
+        .map(|(verb, tense, _)| (verb.as_str(), *tense))
+        .collect();
+    let new = drill::pick_by(verbs, templates, query, |verb, tense| {
+        !seen.contains(&(verb.verb.as_str(), tense))
+    });
+    match new {
+        Ok(drill) => Ok(ReviewItem {
+            drill,
+            new: true,
+            due: None,
+        }),
+        Err(message) => items
+            .iter()
+            .find_map(|(verb, tense, due)| review(verb, *tense, *due, &mut rng))
+            .ok_or(ReviewError::Invalid(message)),
+    }
+}
+
+/// Grade an answer, reschedule its item and store the review
+pub fn answer(
+    store: &ProgressStore,
+    token: Option<&str>,
+    verb: &Verb,
+    templates: &HashMap<String, Template>,
+    answer: &DrillAnswer,
+) -> Result<ReviewOutcome, ReviewError> {
+    let learner = store.authenticate(token)?;
+    let result = drill::check(verb, templates, answer).map_err(ReviewError::Invalid)?;
+    let answer = DrillAnswer {
+        verb: verb.verb.clone(),
+        ..answer.clone()
+    };
+    let quality = quality(&result);
+    let schedule = store
+        .schedule(&learner, &answer.verb, answer.tense)?
+        .review(quality);
+    let due = store.record(&learner, &answer, &result, quality, schedule)?;
+    Ok(ReviewOutcome {
+        result,
+        quality,
+        interval: schedule.interval,
+        due,
+    })
+}
+
+/// Summarize the items and answers of a learner
+pub fn stats(store: &ProgressStore, token: Option<&str>) -> Result<ReviewStats, ReviewError> {
+    let learner = store.authenticate(token)?;
+    let connection = store.connection();
+    let now = now();
+    let (items, due, mature) = connection.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(due <= ?2), 0), COALESCE(SUM(repetitions >= 3), 0)
+         FROM items WHERE learner = ?1",
+        params![learner, now],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    let mut stats = ReviewStats {
+        items,
+        due,
+        mature,
+        ..ReviewStats::default()
+    };
+    let mut statement = connection.prepare(
+        "SELECT tense, COUNT(*), SUM(correct) FROM reviews WHERE learner = ?1
+         GROUP BY tense ORDER BY tense",
+    )?;
+    let rows = statement.query_map(params![learner], |row| {
+        Ok(TenseStats {
+            tense: row.get(0)?,
+            reviews: row.get(1)?,
+            correct: row.get(2)?,
+        })
+    })?;
+    for row in rows {
+        let tense = row?;
+        stats.reviews += tense.reviews;
+        stats.correct += tense.correct;
+        stats.tenses.push(tense);
+    }
+    if stats.reviews > 0 {
+        stats.accuracy = stats.correct as f64 / stats.reviews as f64;
+    }
+
+    let mut statement = connection.prepare(
+        "SELECT mistake, COUNT(*) FROM reviews WHERE learner = ?1 AND mistake IS NOT NULL
+         GROUP BY mistake",
+    )?;
+    let rows = statement.query_map(params![learner], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    for row in rows {
+        let (mistake, count) = row?;
+        stats.mistakes.insert(mistake, count);
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conjugation::{Gender, Person};
+    use crate::template::load_all_templates;
+    use crate::verbs::load_all_verbs;
+
+    const VERBS_XML: &str = include_str!("../../data/verbs-fr.xml");
+    const CONJUGATION_XML: &str = include_str!("../../data/conjugation-fr.xml");
+
+    fn result(mistake: Option<Mistake>) -> DrillResult {
+        DrillResult {
+            correct: mistake.is_none(),
+            answer: String::new(),
+            expected: Vec::new(),
+            mistake,
+        }
+    }
+
+    fn assert_schedule(schedule: Schedule, repetitions: u32, interval: f64, ease: f64) {
+        assert_eq!(schedule.repetitions, repetitions, "{schedule:?}");
+        assert!((schedule.interval - interval).abs() < 1e-9, "{schedule:?}");
+        assert!((schedule.ease - ease).abs() < 1e-9, "{schedule:?}");
+    }
+
+    #[test]
+    fn answer_quality() {
+        let cases = [
+            (None, 5),
+            (Some(Mistake::AccentOnly), 3),
+            (Some(Mistake::WrongAgreement), 2),
+            (Some(Mistake::WrongAuxiliary), 2),
+            (Some(Mistake::WrongEnding), 1),
+            (Some(Mistake::WrongStem), 1),
+            (Some(Mistake::Other), 0),
+        ];
+        for (mistake, expected) in cases {
+            assert_eq!(quality(&result(mistake)), expected, "{mistake:?}");
+        }
+    }
+
+    #[test]
+    fn successful_reviews_lengthen_the_interval() {
+        let first = Schedule::NEW.review(5);
+        assert_schedule(first, 1, 1.0, 2.6);
+        let second = first.review(5);
+        assert_schedule(second, 2, 6.0, 2.7);
+        // The interval grows by the ease before this review; a grade of 4
+        // leaves the ease unchanged
+        let third = second.review(4);
+        assert_schedule(third, 3, 16.0, 2.7);
+        // A grade of 3 still passes but lowers the ease
+        let fourth = third.review(3);
+        assert_schedule(fourth, 4, 43.0, 2.56);
+    }
+
+    #[test]
+    fn failed_reviews_start_over() {
+        let learned = Schedule {
+            repetitions: 4,
+            interval: 43.0,
+            ease: 2.5,
+        };
+        assert_schedule(learned.review(2), 0, 1.0, 2.18);
+        assert_schedule(learned.review(0), 0, 1.0, 1.7);
+        // The ease never drops below 1.3
+        let hard = Schedule {
+            ease: 1.4,
+            ..learned
+        };
+        assert_schedule(hard.review(0), 0, 1.0, 1.3);
+    }
+
+    #[test]
+    fn overdue_items_follow_the_query() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let store = ProgressStore::open(Path::new(":memory:")).unwrap();
+        let token = store.create_learner().unwrap();
+
+        // An indicative item due now
+        let answer = DrillAnswer {
+            verb: "aimer".to_string(),
+            tense: Tense::IndicativePresent,
+            person: Person::FirstSingular,
+            gender: Gender::Masculine,
+            answer: "aime".to_string(),
+        };
+        let due = Schedule {
+            interval: 0.0,
+            ..Schedule::NEW
+        };
+        store
+            .record(&token, &answer, &result(None), 5, due)
+            .unwrap();
+
+        let indicative = DrillQuery {
+            tenses: Some("indicative-present".to_string()),
+            ..DrillQuery::default()
+        };
+        let item = next(&store, Some(&token), &verbs, &templates, &indicative).unwrap();
+        assert!(!item.new);
+        assert_eq!(item.drill.verb, "aimer");
+
+        let subjunctive = DrillQuery {
+            tenses: Some("subjunctive-present".to_string()),
+            ..DrillQuery::default()
+        };
+        let item = next(&store, Some(&token), &verbs, &templates, &subjunctive).unwrap();
+        assert!(item.new);
+        assert_eq!(item.drill.tense, Tense::SubjunctivePresent);
+
+        let other_template = DrillQuery {
+            template: Some("fin:ir".to_string()),
+            ..indicative
+        };
+        let item = next(&store, Some(&token), &verbs, &templates, &other_template).unwrap();
+        assert!(item.new);
+        assert_eq!(item.drill.template, "fin:ir");
+    }
+
+    #[test]
+    fn stats_aggregate_the_answers_of_a_learner() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let store = ProgressStore::open(Path::new(":memory:")).unwrap();
+        let token = store.create_learner().unwrap();
+        let other = store.create_learner().unwrap();
+
+        let answers = [
+            (
+                "aimer",
+                Tense::IndicativePresent,
+                Person::FirstSingular,
+                "aime",
+            ),
+            (
+                "céder",
+                Tense::IndicativePresent,
+                Person::FirstSingular,
+                "cede",
+            ),
+            (
+                "finir",
+                Tense::SubjunctivePresent,
+                Person::FirstSingular,
+                "finisse",
+            ),
+            (
+                "finir",
+                Tense::SubjunctivePresent,
+                Person::ThirdPlural,
+                "xyz",
+            ),
+        ];
+        for (verb, tense, person, text) in answers {
+            let verb = verbs.iter().find(|v| v.verb == verb).unwrap();
+            let drill_answer = DrillAnswer {
+                verb: verb.verb.clone(),
+                tense,
+                person,
+                gender: Gender::Masculine,
+                answer: text.to_string(),
+            };
+            answer(&store, Some(&token), verb, &templates, &drill_answer).unwrap();
+        }
+        let aimer = verbs.iter().find(|v| v.verb == "aimer").unwrap();
+        let wrong = DrillAnswer {
+            verb: "aimer".to_string(),
+            tense: Tense::IndicativeFuture,
+            person: Person::FirstSingular,
+            gender: Gender::Masculine,
+            answer: "xyz".to_string(),
+        };
+        answer(&store, Some(&other), aimer, &templates, &wrong).unwrap();
+
+        let progress = stats(&store, Some(&token)).unwrap();
+        assert_eq!((progress.items, progress.due, progress.mature), (3, 0, 0));
+        assert_eq!((progress.reviews, progress.correct), (4, 2));
+        assert!((progress.accuracy - 0.5).abs() < 1e-9);
+        let mistakes: Vec<(&str, u64)> = progress
+            .mistakes
+            .iter()
+            .map(|(mistake, count)| (mistake.as_str(), *count))
+            .collect();
+        assert_eq!(mistakes, [("accent-only", 1), ("other", 1)]);
+        let tenses: Vec<(&str, u64, u64)> = progress
+            .tenses
+            .iter()
+            .map(|tense| (tense.tense.as_str(), tense.reviews, tense.correct))
+            .collect();
+        assert_eq!(
+            tenses,
+            [("indicative-present", 2, 1), ("subjunctive-present", 2, 1)]
+        );
+
+        assert!(matches!(
+            stats(&store, Some("unknown")),
+            Err(ReviewError::Unauthorized)
+        ));
+    }
+}
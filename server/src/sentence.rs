@@ -0,0 +1,266 @@
+use crate::pronouns;
+use serde::{Deserialize, Serialize};
+
+/// Sentence form in which conjugated forms are displayed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SentenceForm {
+    /// je mange
+    #[default]
+    Affirmative,
+    /// je ne mange pas
+    Negative,
+    /// mange-t-il ?
+    Interrogative,
+    /// ne mange-t-il pas ?
+    NegativeInterrogative,
+    /// est-ce qu'il mange ?
+    EstCeQue,
+    /// est-ce qu'il ne mange pas ?
+    NegativeEstCeQue,
+}
+
+impl SentenceForm {
+    pub fn is_negative(self) -> bool {
+        matches!(
+            self,
+            SentenceForm::Negative
+                | SentenceForm::NegativeInterrogative
+                | SentenceForm::NegativeEstCeQue
+        )
+    }
+
+    pub fn is_interrogative(self) -> bool {
+        !matches!(self, SentenceForm::Affirmative | SentenceForm::Negative)
+    }
+}
+
+/// Position of the subject pronoun in a rendered phrase
+#[derive(Debug, Clone, Copy)]
+pub enum Subject<'a> {
+    /// Imperative and non-finite forms
+    None,
+    /// Statement order: "il mange"
+    Before(&'a str),
+    /// Inversion: "mange-t-il"
+    Inverted(&'a str),
+}
+
+/// A verb phrase split into the parts between which negation and inversion
+/// insert words: "ne" + reflexive + finite verb (+ inverted subject) + "pas" + participle
+#[derive(Debug, Clone)]
+pub struct Phrase {
+    /// Unstressed reflexive pronoun placed before the finite verb ("me", "se")
+    pub reflexive: Option<&'static str>,
+    /// Conjugated verb, or auxiliary in compound tenses
+    pub finite: String,
+    /// Whether `finite` is the verb itself, so that an aspirate h prevents elision
+    pub finite_is_verb: bool,
+    /// Stressed pronoun hyphenated after an affirmative imperative ("lève-toi")
+    pub stressed: Option<&'static str>,
+    /// Past participle of compound tenses
+    pub participle: Option<String>,
+}
+
+impl Phrase {
+    /// Render the phrase, negated if requested, with the subject at the given position
+    pub fn render(&self, subject: Subject, negative: bool, aspirate_h: bool) -> String {
+        let mut aspirate_h = aspirate_h && self.finite_is_verb;
+        let mut text = self.finite.clone();
+        if let Some(pronoun) = self.stressed {
+            text = format!("{text}-{pronoun}");
+        }
+        if let Subject::Inverted(subject) = subject {
+            text = invert(&text, subject);
+        }
+        for clitic in [self.reflexive, negative.then_some("ne")]
+            .into_iter()
+            .flatten()
+        {
+            text = pronouns::elide(clitic, &text, aspirate_h);
+            aspirate_h = false;
+        }
+        if negative {
+            text.push_str(" pas");
+        }
+        if let Some(participle) = &self.participle {
+            text = format!("{text} {participle}");
+        }
+        if let Subject::Before(subject) = subject {
+            text = pronouns::elide(subject, &text, aspirate_h);
+        }
+        text
+    }
+
+    /// Render the phrase as a question, by inversion when the form allows it and
+    /// with "est-ce que" otherwise
+    pub fn question(&self, subject: &str, form: SentenceForm, aspirate_h: bool) -> String {
+        let negative = form.is_negative();
+        let question = match form {
+            SentenceForm::Interrogative | SentenceForm::NegativeInterrogative
+                if can_invert(&self.finite, subject) =>
+            {
+                self.render(Subject::Inverted(subject), negative, aspirate_h)
+            }
+            _ => {
+                let statement = self.render(Subject::Before(subject), negative, aspirate_h);
+                format!("est-ce {}", pronouns::elide("que", &statement, false))
+            }
+        };
+        format!("{question} ?")
+    }
+}
+
+/// Whether a verb form can be inverted with its subject. Inversion with "je" is
+/// limited to forms not ending in a mute e ("ai-je", "suis-je", but not "mange-je");
+/// "peux" is never inverted, "puis-je" being used instead.
+fn can_invert(finite: &str, subject: &str) -> bool {
+    subject != "je" || !(finite.ends_with('e') || finite == "peux")
+}
+
+/// Hyphenate a subject pronoun after the verb, inserting the euphonic "-t-" between
+/// a vowel and a vowel-initial pronoun ("mange-t-il", "a-t-on", but "prend-il")
+fn invert(finite: &str, subject: &str) -> String {
+    let ends_with_vowel = finite
+        .chars()
+        .last()
+        .is_some_and(|c| pronouns::starts_with_vowel_sound(&c.to_string(), false));
+    if ends_with_vowel && pronouns::starts_with_vowel_sound(subject, false) {
+        format!("{finite}-t-{subject}")
+    } else {
+        format!("{finite}-{subject}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phrase(finite: &str, participle: Option<&str>) -> Phrase {
+        Phrase {
+            reflexive: None,
+            finite: finite.to_string(),
+            finite_is_verb: participle.is_none(),
+            stressed: None,
+            participle: participle.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn inversion_and_euphonic_t() {
+        let cases = [
+            ("mange", "il", "mange-t-il"),
+            ("a", "on", "a-t-on"),
+            ("aima", "elle", "aima-t-elle"),
+            ("prend", "il", "prend-il"),
+            ("vont", "ils", "vont-ils"),
+            ("mange", "nous", "mange-nous"),
+            ("ai", "je", "ai-je"),
+        ];
+        for (finite, subject, expected) in cases {
+            assert_eq!(invert(finite, subject), expected, "{finite} {subject}");
+        }
+    }
+
+    #[test]
+    fn inversion_with_je() {
+        let cases = [
+            ("ai", true),
+            ("suis", true),
+            ("mange", false),
+            ("peux", false),
+        ];
+        for (finite, expected) in cases {
+            assert_eq!(can_invert(finite, "je"), expected, "{finite}");
+        }
+        assert!(can_invert("mange", "il"));
+    }
+
+    #[test]
+    fn sentence_forms() {
+        let cases = [
+            (
+                phrase("mange", None),
+                "il",
+                SentenceForm::Affirmative,
+                false,
+                "il mange",
+            ),
+            (
+                phrase("aime", None),
+                "je",
+                SentenceForm::Negative,
+                false,
+                "je n'aime pas",
+            ),
+            (
+                phrase("hais", None),
+                "je",
+                SentenceForm::Negative,
+                true,
+                "je ne hais pas",
+            ),
+            (
+                phrase("mange", None),
+                "il",
+                SentenceForm::Interrogative,
+                false,
+                "mange-t-il ?",
+            ),
+            (
+                phrase("mange", None),
+                "je",
+                SentenceForm::Interrogative,
+                false,
+                "est-ce que je mange ?",
+            ),
+            (
+                phrase("aime", None),
+                "il",
+                SentenceForm::EstCeQue,
+                false,
+                "est-ce qu'il aime ?",
+            ),
+            (
+                phrase("a", Some("aimé")),
+                "il",
+                SentenceForm::NegativeInterrogative,
+                false,
+                "n'a-t-il pas aimé ?",
+            ),
+            (
+                phrase("a", Some("haï")),
+                "elle",
+                SentenceForm::NegativeEstCeQue,
+                true,
+                "est-ce qu'elle n'a pas haï ?",
+            ),
+        ];
+        for (phrase, subject, form, aspirate_h, expected) in cases {
+            let text = if form.is_interrogative() {
+                phrase.question(subject, form, aspirate_h)
+            } else {
+                phrase.render(Subject::Before(subject), form.is_negative(), aspirate_h)
+            };
+            assert_eq!(text, expected);
+        }
+    }
+
+    #[test]
+    fn reflexive_and_stressed_pronouns() {
+        let mut lève = phrase("lève", None);
+        lève.reflexive = Some("me");
+        assert_eq!(
+            lève.render(Subject::Before("je"), false, false),
+            "je me lève"
+        );
+        assert_eq!(
+            lève.render(Subject::Before("je"), true, false),
+            "je ne me lève pas"
+        );
+
+        let mut lève = phrase("lève", None);
+        lève.stressed = Some("toi");
+        assert_eq!(lève.render(Subject::None, false, false), "lève-toi");
+    }
+}
@@ -0,0 +1,316 @@
+use crate::conjugation::Tense;
+use serde::{Deserialize, Serialize};
+
+/// Spelling of the conjugated forms, with respect to the 1990 rectifications
+/// orthographiques
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Spelling {
+    /// Traditional spelling only ("je céderai", "il ruisselle", "il paraît")
+    #[default]
+    Traditional,
+    /// 1990 spelling only ("je cèderai", "il ruissèle", "il parait")
+    Reformed,
+    /// Traditional spelling followed by the differing 1990 variants
+    Both,
+}
+
+/// Spelling requested for search results, read from the query string
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct SpellingQuery {
+    pub spelling: Spelling,
+}
+
+/// "-eler" and "-eter" verbs that keep the doubled consonant in the 1990 spelling:
+/// "appeler", "jeter" and their compounds
+const DOUBLING_VERBS: &[&str] = &[
+    "appeler",
+    "déjeter",
+    "forjeter",
+    "interjeter",
+    "interpeler",
+    "introjeter",
+    "jeter",
+    "projeter",
+    "rappeler",
+    "rejeter",
+    "surjeter",
+];
+
+/// Verbs whose circumflex is kept, to tell their forms from those of "croire"
+const CIRCUMFLEX_VERBS: &[&str] = &["croître"];
+
+/// Past participles keeping their circumflex, to tell them from "du", "mu" and
+/// "cru"
+const CIRCUMFLEX_PARTICIPLES: &[&str] = &["dû", "redû", "mû", "crû", "recrû"];
+
+/// Endings of the simple past and imperfect subjunctive, which keep their
+/// circumflex ("nous parûmes", "qu'il finît")
+const CIRCUMFLEX_ENDINGS: &[&str] = &["îmes", "îtes", "ît", "ûmes", "ûtes", "ût"];
+
+fn is_vowel(c: char) -> bool {
+    "aeiouyàâäéèêëîïôöùûüœæ".contains(c)
+}
+
+/// Split "céder" into ("c", "d"): the part before the "é" of the last stem
+/// syllable and the consonants between it and the "-er" termination. `None` when
+/// the verb does not follow the "céder" pattern.
+fn acute_stem(verb: &str) -> Option<(&str, &str)> {
+    let stem = verb.strip_suffix("er")?;
+    let (prefix, consonants) = stem.rsplit_once('é')?;
+    // "gu" and "qu" count as consonants ("léguer", "disséquer")
+    let consonants_only = consonants
+        .trim_end_matches("gu")
+        .trim_end_matches("qu")
+        .chars()
+        .all(|c| !is_vowel(c));
+    (!consonants.is_empty() && consonants_only).then_some((prefix, consonants))
+}
+
+/// Replace the accent of the last stem syllable in the future and conditional of
+/// "céder"-type verbs: `from` "é" and `to` "è", or the reverse
+fn swap_stem_accent(verb: &str, tense: Tense, form: &str, from: char, to: char) -> String {
+    if !matches!(tense, Tense::IndicativeFuture | Tense::ConditionalPresent) {
+        return form.to_string();
+    }
+    let Some((prefix, consonants)) = acute_stem(verb) else {
+        return form.to_string();
+    };
+    let before = format!("{prefix}{from}{consonants}e");
+    match form.strip_prefix(&before) {
+        Some(rest) => format!("{prefix}{to}{consonants}e{rest}"),
+        None => form.to_string(),
+    }
+}
+
+/// "ruisselle" -> "ruissèle" for "-eler"/"-eter" verbs doubling their consonant
+fn single_consonant(verb: &str, form: &str) -> String {
+    if DOUBLING_VERBS.contains(&verb) {
+        return form.to_string();
+    }
+    let Some((stem, consonant)) = verb
+        .strip_suffix("eler")
+        .map(|stem| (stem, 'l'))
+        .or_else(|| verb.strip_suffix("eter").map(|stem| (stem, 't')))
+    else {
+        return form.to_string();
+    };
+    let doubled = format!("{stem}e{consonant}{consonant}");
+    match form.strip_prefix(&doubled) {
+        Some(rest) => format!("{stem}è{consonant}{rest}"),
+        None => form.to_string(),
+    }
+}
+
+/// Drop the circumflex of "î" and "û" ("il paraît" -> "il parait", "coûter" ->
+/// "couter"), except in the endings of the simple past and imperfect
+/// subjunctive and in the forms that would be homographs of other words
+fn drop_circumflex(verb: &str, tense: Tense, form: &str) -> String {
+    if CIRCUMFLEX_VERBS.contains(&verb)
+        || (tense == Tense::PastParticiple && CIRCUMFLEX_PARTICIPLES.contains(&form))
+    {
+        return form.to_string();
+    }
+    let ending = match tense {
+        Tense::IndicativeSimplePast | Tense::SubjunctiveImperfect => CIRCUMFLEX_ENDINGS
+            .iter()
+            .find(|ending| form.ends_with(*ending))
+            .copied()
+            .unwrap_or(""),
+        _ => "",
+    };
+    let stem = &form[..form.len() - ending.len()];
+    format!("{}{ending}", stem.replace('î', "i").replace('û', "u"))
+}
+
+/// Traditional spelling of a form. The data already lists some 1990 variants
+/// ("je cèderai" next to "je céderai"), which are mapped back here before the
+/// variants of both spellings are told apart.
+pub fn traditional(verb: &str, tense: Tense, form: &str) -> String {
+    swap_stem_accent(verb, tense, form, 'è', 'é')
+}
+
+/// 1990 spelling of a traditionally spelled form
+pub fn reformed(verb: &str, tense: Tense, form: &str) -> String {
+    let form = swap_stem_accent(verb, tense, form, 'é', 'è');
+    let form = single_consonant(verb, &form);
+    drop_circumflex(verb, tense, &form)
+}
+
+/// 1990 spelling of an infinitive, when it differs ("paraître" -> "paraitre")
+pub fn reformed_infinitive(verb: &str) -> Option<String> {
+    let reformed = reformed(verb, Tense::InfinitivePresent, verb);
+    (reformed != verb).then_some(reformed)
+}
+
+/// Variants of one cell in the requested spelling, and the subset of them that
+/// are 1990 spellings differing from the traditional ones. The traditional
+/// spelling is the cell as the data gives it, variants included.
+pub fn spell(
+    verb: &str,
+    tense: Tense,
+    cell: &[String],
+    spelling: Spelling,
+) -> (Vec<String>, Vec<String>) {
+    if spelling == Spelling::Traditional {
+        return (cell.to_vec(), Vec::new());
+    }
+    let mut traditional_forms: Vec<String> = Vec::new();
+    for form in cell {
+        let form = traditional(verb, tense, form);
+        if !traditional_forms.contains(&form) {
+            traditional_forms.push(form);
+        }
+    }
+    let mut reformed_forms: Vec<String> = Vec::new();
+    for form in &traditional_forms {
+        let form = reformed(verb, tense, form);
+        if !traditional_forms.contains(&form) && !reformed_forms.contains(&form) {
+            reformed_forms.push(form);
+        }
+    }
+
+    if spelling == Spelling::Reformed {
+        let forms = traditional_forms
+            .iter()
+            .map(|form| reformed(verb, tense, form))
+            .fold(Vec::new(), |mut forms, form| {
+                if !forms.contains(&form) {
+                    forms.push(form);
+                }
+                forms
+            });
+        return (forms, reformed_forms);
+    }
+    let forms = traditional_forms
+        .into_iter()
+        .chain(reformed_forms.iter().cloned())
+        .collect();
+    (forms, reformed_forms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(forms: &[&str]) -> Vec<String> {
+        forms.iter().map(|form| form.to_string()).collect()
+    }
+
+    #[test]
+    fn reformed_forms() {
+        let cases = [
+            // Grave accent in the future and conditional of "céder" verbs
+            ("céder", Tense::IndicativeFuture, "céderai", "cèderai"),
+            ("céder", Tense::ConditionalPresent, "céderions", "cèderions"),
+            ("léguer", Tense::IndicativeFuture, "léguera", "lèguera"),
+            ("céder", Tense::IndicativePresent, "cédons", "cédons"),
+            ("créer", Tense::IndicativeFuture, "créerai", "créerai"),
+            // Single consonant and grave accent for "-eler" and "-eter" verbs
+            (
+                "ruisseler",
+                Tense::IndicativePresent,
+                "ruisselle",
+                "ruissèle",
+            ),
+            (
+                "étiqueter",
+                Tense::IndicativeFuture,
+                "étiquetterai",
+                "étiquèterai",
+            ),
+            ("appeler", Tense::IndicativePresent, "appelle", "appelle"),
+            ("jeter", Tense::IndicativePresent, "jette", "jette"),
+            // No circumflex on "i" and "u", but in "croître"
+            ("paraître", Tense::IndicativePresent, "paraît", "parait"),
+            ("plaire", Tense::IndicativePresent, "plaît", "plait"),
+            ("paraître", Tense::InfinitivePresent, "paraître", "paraitre"),
+            ("croître", Tense::IndicativePresent, "croît", "croît"),
+            ("coûter", Tense::IndicativePresent, "coûte", "coute"),
+            ("coûter", Tense::InfinitivePresent, "coûter", "couter"),
+            ("traîner", Tense::IndicativeFuture, "traînerai", "trainerai"),
+            (
+                "traîner",
+                Tense::IndicativeSimplePast,
+                "traînâmes",
+                "trainâmes",
+            ),
+            ("mûrir", Tense::IndicativePresent, "mûrit", "murit"),
+            ("mûrir", Tense::PastParticiple, "mûrie", "murie"),
+            // but in the endings of the simple past and imperfect subjunctive
+            ("mûrir", Tense::IndicativeSimplePast, "mûrîmes", "murîmes"),
+            ("mûrir", Tense::SubjunctiveImperfect, "mûrît", "murît"),
+            (
+                "paraître",
+                Tense::IndicativeSimplePast,
+                "parûtes",
+                "parûtes",
+            ),
+            // and in "dû" and "mû"
+            ("devoir", Tense::PastParticiple, "dû", "dû"),
+            ("mouvoir", Tense::PastParticiple, "mû", "mû"),
+            ("devoir", Tense::PastParticiple, "dues", "dues"),
+        ];
+        for (verb, tense, form, expected) in cases {
+            assert_eq!(reformed(verb, tense, form), expected, "{verb} {form}");
+        }
+    }
+
+    #[test]
+    fn traditional_forms() {
+        assert_eq!(
+            traditional("céder", Tense::IndicativeFuture, "cèderai"),
+            "céderai"
+        );
+        assert_eq!(
+            traditional("céder", Tense::IndicativePresent, "cède"),
+            "cède"
+        );
+    }
+
+    #[test]
+    fn reformed_infinitives() {
+        assert_eq!(
+            reformed_infinitive("paraître"),
+            Some("paraitre".to_string())
+        );
+        assert_eq!(
+            reformed_infinitive("connaître"),
+            Some("connaitre".to_string())
+        );
+        assert_eq!(reformed_infinitive("traîner"), Some("trainer".to_string()));
+        assert_eq!(reformed_infinitive("croître"), None);
+        assert_eq!(reformed_infinitive("aimer"), None);
+    }
+
+    #[test]
+    fn spellings_of_a_cell() {
+        // The data lists the 1990 variant of "céder" next to the traditional form
+        let data = cell(&["céderai", "cèderai"]);
+        let future = Tense::IndicativeFuture;
+        assert_eq!(
+            spell("céder", future, &data, Spelling::Traditional),
+            (data.clone(), Vec::new())
+        );
+        assert_eq!(
+            spell("céder", future, &data, Spelling::Reformed),
+            (cell(&["cèderai"]), cell(&["cèderai"]))
+        );
+        assert_eq!(
+            spell("céder", future, &data, Spelling::Both),
+            (cell(&["céderai", "cèderai"]), cell(&["cèderai"]))
+        );
+
+        let present = Tense::IndicativePresent;
+        let data = cell(&["paraît"]);
+        assert_eq!(
+            spell("paraître", present, &data, Spelling::Traditional),
+            (data.clone(), Vec::new())
+        );
+        assert_eq!(
+            spell("paraître", present, &data, Spelling::Both),
+            (cell(&["paraît", "parait"]), cell(&["parait"]))
+        );
+    }
+}
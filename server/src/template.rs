@@ -1,11 +1,13 @@
 use quick_xml::Reader;
+use quick_xml::escape::partial_escape;
 use quick_xml::events::Event;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct Template {
+    #[serde(default)]
     pub name: String,
     pub infinitive: Infinitive,
     pub indicative: Indicative,
@@ -15,13 +17,13 @@ pub struct Template {
     pub participle: Participle,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct Infinitive {
     pub infinitive_present: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct Indicative {
     pub present: Vec<Vec<String>>,
@@ -30,32 +32,79 @@ pub struct Indicative {
     pub simple_past: Vec<Vec<String>>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct Conditional {
     pub present: Vec<Vec<String>>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct Subjunctive {
     pub present: Vec<Vec<String>>,
     pub imperfect: Vec<Vec<String>>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct Imperative {
     pub imperative_present: Vec<Vec<String>>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct Participle {
     pub present_participle: Vec<String>,
     pub past_participle: Vec<Vec<String>>,
 }
 
+/// A missing cell as `/api/t` has always served it: one empty ending
+fn placeholder(cell: &[String]) -> Vec<String> {
+    if cell.is_empty() {
+        vec![String::new()]
+    } else {
+        cell.to_vec()
+    }
+}
+
+fn placeholders(cells: &[Vec<String>]) -> Vec<Vec<String>> {
+    cells.iter().map(|cell| placeholder(cell)).collect()
+}
+
+impl Template {
+    /// The template in the layout of `/api/t`, which the frontend reads: a
+    /// missing cell (`<p></p>`) is `[""]` there rather than `[]`
+    pub fn with_placeholders(&self) -> Template {
+        Template {
+            name: self.name.clone(),
+            infinitive: Infinitive {
+                infinitive_present: placeholder(&self.infinitive.infinitive_present),
+            },
+            indicative: Indicative {
+                present: placeholders(&self.indicative.present),
+                imperfect: placeholders(&self.indicative.imperfect),
+                future: placeholders(&self.indicative.future),
+                simple_past: placeholders(&self.indicative.simple_past),
+            },
+            conditional: Conditional {
+                present: placeholders(&self.conditional.present),
+            },
+            subjunctive: Subjunctive {
+                present: placeholders(&self.subjunctive.present),
+                imperfect: placeholders(&self.subjunctive.imperfect),
+            },
+            imperative: Imperative {
+                imperative_present: placeholders(&self.imperative.imperative_present),
+            },
+            participle: Participle {
+                present_participle: placeholder(&self.participle.present_participle),
+                past_participle: placeholders(&self.participle.past_participle),
+            },
+        }
+    }
+}
+
+#[allow(clippy::collapsible_match)]
 pub fn load_all_templates(
     xml_data: &str,
 ) -> Result<HashMap<String, Template>, Box<dyn std::error::Error>> {
@@ -161,13 +210,19 @@ pub fn load_all_templates(
                 }
                 b"i" => {
                     in_i = true;
+                    // An empty <i></i> is an empty ending: the form is the bare stem
+                    if in_p {
+                        current_p_elements.push(String::new());
+                    }
                 }
                 _ => {}
             },
             Ok(Event::Text(e)) => {
                 if in_i && in_p {
                     let text = e.unescape()?.to_string();
-                    current_p_elements.push(text.trim().to_string());
+                    if let Some(ending) = current_p_elements.last_mut() {
+                        ending.push_str(text.trim());
+                    }
                 }
             }
             Ok(Event::End(e)) => {
@@ -232,85 +287,22 @@ pub fn load_all_templates(
                     }
                     b"p" => {
                         in_p = false;
-                        // Always process, even if empty - empty <p></p> should add empty string/vector
+                        // An empty <p></p> is a missing form and gives an empty cell
+                        let cell = current_p_elements.clone();
                         match current_section {
-                            Some("infinitive-present") => {
-                                if current_p_elements.is_empty() {
-                                    current_infinitive_present.push(String::new());
-                                } else {
-                                    current_infinitive_present.extend(current_p_elements.clone());
-                                }
-                            }
-                            Some("indicative-present") => {
-                                if current_p_elements.is_empty() {
-                                    current_present.push(vec![String::new()]);
-                                } else {
-                                    current_present.push(current_p_elements.clone());
-                                }
-                            }
-                            Some("indicative-imperfect") => {
-                                if current_p_elements.is_empty() {
-                                    current_imperfect.push(vec![String::new()]);
-                                } else {
-                                    current_imperfect.push(current_p_elements.clone());
-                                }
-                            }
-                            Some("indicative-future") => {
-                                if current_p_elements.is_empty() {
-                                    current_future.push(vec![String::new()]);
-                                } else {
-                                    current_future.push(current_p_elements.clone());
-                                }
-                            }
-                            Some("indicative-simple-past") => {
-                                if current_p_elements.is_empty() {
-                                    current_simple_past.push(vec![String::new()]);
-                                } else {
-                                    current_simple_past.push(current_p_elements.clone());
-                                }
-                            }
-                            Some("conditional-present") => {
-                                if current_p_elements.is_empty() {
-                                    current_conditional_present.push(vec![String::new()]);
-                                } else {
-                                    current_conditional_present.push(current_p_elements.clone());
-                                }
-                            }
-                            Some("subjunctive-present") => {
-                                if current_p_elements.is_empty() {
-                                    current_subjunctive_present.push(vec![String::new()]);
-                                } else {
-                                    current_subjunctive_present.push(current_p_elements.clone());
-                                }
-                            }
+                            Some("infinitive-present") => current_infinitive_present.extend(cell),
+                            Some("indicative-present") => current_present.push(cell),
+                            Some("indicative-imperfect") => current_imperfect.push(cell),
+                            Some("indicative-future") => current_future.push(cell),
+                            Some("indicative-simple-past") => current_simple_past.push(cell),
+                            Some("conditional-present") => current_conditional_present.push(cell),
+                            Some("subjunctive-present") => current_subjunctive_present.push(cell),
                             Some("subjunctive-imperfect") => {
-                                if current_p_elements.is_empty() {
-                                    current_subjunctive_imperfect.push(vec![String::new()]);
-                                } else {
-                                    current_subjunctive_imperfect.push(current_p_elements.clone());
-                                }
-                            }
-                            Some("imperative-present") => {
-                                if current_p_elements.is_empty() {
-                                    current_imperative_present.push(vec![String::new()]);
-                                } else {
-                                    current_imperative_present.push(current_p_elements.clone());
-                                }
-                            }
-                            Some("present-participle") => {
-                                if current_p_elements.is_empty() {
-                                    current_present_participle.push(String::new());
-                                } else {
-                                    current_present_participle.extend(current_p_elements.clone());
-                                }
-                            }
-                            Some("past-participle") => {
-                                if current_p_elements.is_empty() {
-                                    current_past_participle.push(vec![String::new()]);
-                                } else {
-                                    current_past_participle.push(current_p_elements.clone());
-                                }
+                                current_subjunctive_imperfect.push(cell)
                             }
+                            Some("imperative-present") => current_imperative_present.push(cell),
+                            Some("present-participle") => current_present_participle.extend(cell),
+                            Some("past-participle") => current_past_participle.push(cell),
                             _ => {}
                         }
                         current_p_elements.clear();
@@ -330,3 +322,136 @@ pub fn load_all_templates(
 
     Ok(templates)
 }
+
+/// Write one `<p>` element; an empty cell is written as `<p></p>` and an empty
+/// ending as `<i></i>`
+fn write_p(xml: &mut String, indent: &str, cell: &[String]) {
+    xml.push_str(indent);
+    xml.push_str("<p>");
+    for ending in cell {
+        xml.push_str("<i>");
+        xml.push_str(&partial_escape(ending));
+        xml.push_str("</i>");
+    }
+    xml.push_str("</p>\n");
+}
+
+/// Write a tense element containing one `<p>` per person
+fn write_tense(xml: &mut String, indent: &str, name: &str, cells: &[Vec<String>]) {
+    xml.push_str(&format!("{indent}<{name}>\n"));
+    for cell in cells {
+        write_p(xml, &format!("{indent}\t"), cell);
+    }
+    xml.push_str(&format!("{indent}</{name}>\n"));
+}
+
+/// Serialize a single template to a `<template>` block as found in conjugation-fr.xml
+pub fn write_template(template: &Template) -> String {
+    let mut xml = String::new();
+    xml.push_str(&format!(
+        "<template name=\"{}\">\n",
+        partial_escape(&template.name)
+    ));
+
+    xml.push_str("\t<infinitive>\n");
+    write_tense(
+        &mut xml,
+        "\t\t",
+        "infinitive-present",
+        std::slice::from_ref(&template.infinitive.infinitive_present),
+    );
+    xml.push_str("\t</infinitive>\n");
+
+    xml.push_str("\t<indicative>\n");
+    write_tense(&mut xml, "\t\t", "present", &template.indicative.present);
+    write_tense(
+        &mut xml,
+        "\t\t",
+        "imperfect",
+        &template.indicative.imperfect,
+    );
+    write_tense(&mut xml, "\t\t", "future", &template.indicative.future);
+    write_tense(
+        &mut xml,
+        "\t\t",
+        "simple-past",
+        &template.indicative.simple_past,
+    );
+    xml.push_str("\t</indicative>\n");
+
+    xml.push_str("\t<conditional>\n");
+    write_tense(&mut xml, "\t\t", "present", &template.conditional.present);
+    xml.push_str("\t</conditional>\n");
+
+    xml.push_str("\t<subjunctive>\n");
+    write_tense(&mut xml, "\t\t", "present", &template.subjunctive.present);
+    write_tense(
+        &mut xml,
+        "\t\t",
+        "imperfect",
+        &template.subjunctive.imperfect,
+    );
+    xml.push_str("\t</subjunctive>\n");
+
+    xml.push_str("\t<imperative>\n");
+    write_tense(
+        &mut xml,
+        "\t\t",
+        "imperative-present",
+        &template.imperative.imperative_present,
+    );
+    xml.push_str("\t</imperative>\n");
+
+    xml.push_str("\t<participle>\n");
+    write_tense(
+        &mut xml,
+        "\t\t",
+        "present-participle",
+        std::slice::from_ref(&template.participle.present_participle),
+    );
+    write_tense(
+        &mut xml,
+        "\t\t",
+        "past-participle",
+        &template.participle.past_participle,
+    );
+    xml.push_str("\t</participle>\n");
+
+    xml.push_str("</template>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONJUGATION_XML: &str = include_str!("../../data/conjugation-fr.xml");
+
+    #[test]
+    fn templates_round_trip() {
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let blocks: String = templates.values().map(write_template).collect();
+        let xml = format!("<conjugation-fr>\n{blocks}</conjugation-fr>\n");
+        let reparsed = load_all_templates(&xml).unwrap();
+        assert_eq!(templates, reparsed);
+    }
+
+    #[test]
+    fn missing_cells_keep_their_api_layout() {
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        let falloir = &templates["fa:lloir"];
+        assert!(falloir.participle.present_participle.is_empty());
+        assert!(falloir.indicative.present[0].is_empty());
+
+        let served = serde_json::to_value(falloir.with_placeholders()).unwrap();
+        assert_eq!(
+            served["participle"]["present_participle"],
+            serde_json::json!([""])
+        );
+        assert_eq!(served["indicative"]["present"][0], serde_json::json!([""]));
+        assert_eq!(
+            served["indicative"]["present"][2],
+            serde_json::json!(["ut"])
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use crate::template::{Template, load_all_templates};
+use crate::verbs::{Verb, load_all_verbs};
+use std::collections::HashMap;
+
+/// Every verb of the embedded data
+pub fn all_verbs() -> Vec<Verb> {
+    load_all_verbs(crate::VERBS_XML).unwrap()
+}
+
+/// The named verbs of the embedded data, in data order
+pub fn verbs(names: &[&str]) -> Vec<Verb> {
+    all_verbs()
+        .into_iter()
+        .filter(|v| names.contains(&v.verb.as_str()))
+        .collect()
+}
+
+/// Every template of the embedded data
+pub fn templates() -> HashMap<String, Template> {
+    load_all_templates(crate::CONJUGATION_XML).unwrap()
+}
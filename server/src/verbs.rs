@@ -1,9 +1,13 @@
+use crate::spelling;
 use deunicode::deunicode;
 use quick_xml::Reader;
+use quick_xml::escape::partial_escape;
 use quick_xml::events::Event;
 use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 #[allow(dead_code)]
 pub struct Verb {
     pub verb: String,
@@ -28,6 +32,8 @@ struct NormalizedIndexEntry {
 pub struct VerbSearchIndex {
     verbs: Vec<Verb>,
     normalized_index: Vec<NormalizedIndexEntry>,
+    /// Index in `verbs` of each infinitive by its 1990 spelling, when it differs
+    reformed: HashMap<String, usize>,
 }
 
 impl VerbSearchIndex {
@@ -46,24 +52,50 @@ impl VerbSearchIndex {
             })
             .collect();
 
+        // 1990 spellings ("paraitre") find their infinitive too
+        let reformed: HashMap<String, usize> = verbs
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, verb)| Some((spelling::reformed_infinitive(&verb.verb)?, idx)))
+            .collect();
+        for (reformed, &idx) in &reformed {
+            let normalized = normalize_string(reformed);
+            if normalized != normalized_index[idx].normalized {
+                normalized_index.push(NormalizedIndexEntry {
+                    normalized,
+                    index: idx,
+                });
+            }
+        }
+
         // Sort by normalized form for fast binary search
         normalized_index.sort_by(|a, b| a.normalized.cmp(&b.normalized));
 
         Self {
             verbs,
             normalized_index,
+            reformed,
         }
     }
 
     /// Get the original verbs vector (sorted by original form)
-    #[allow(dead_code)]
     pub fn verbs(&self) -> &Vec<Verb> {
         &self.verbs
     }
 
-    /// Search for verbs matching the normalized query prefix
+    /// Verb with the given infinitive, in the traditional or the 1990 spelling
+    pub fn find(&self, infinitive: &str) -> Option<&Verb> {
+        let index = self
+            .verbs
+            .binary_search_by(|verb| verb.verb.as_str().cmp(infinitive))
+            .ok()
+            .or_else(|| self.reformed.get(infinitive).copied())?;
+        Some(&self.verbs[index])
+    }
+
+    /// Search for verbs matching the normalized query prefix and accepted by `filter`
     /// Returns up to 20 results sorted by original verb name
-    pub fn search(&self, query: &str) -> Vec<&Verb> {
+    pub fn search_by(&self, query: &str, filter: impl Fn(&Verb) -> bool) -> Vec<&Verb> {
         let query_normalized = normalize_string(query);
         let mut results = Vec::new();
 
@@ -89,7 +121,11 @@ impl VerbSearchIndex {
         // Collect matches starting from first_match
         for entry in self.normalized_index.iter().skip(first_match) {
             if entry.normalized.starts_with(&query_normalized) {
-                results.push(&self.verbs[entry.index]);
+                let verb = &self.verbs[entry.index];
+                if !filter(verb) {
+                    continue;
+                }
+                results.push(verb);
                 if results.len() >= 20 {
                     break;
                 }
@@ -99,13 +135,16 @@ impl VerbSearchIndex {
             }
         }
 
-        // Sort results by original verb name for consistent ordering
+        // Sort results by original verb name for consistent ordering; a verb
+        // matching in both spellings is listed once
         results.sort_by(|a, b| a.verb.cmp(&b.verb));
+        results.dedup_by(|a, b| a.verb == b.verb);
 
         results
     }
 }
 
+#[allow(clippy::collapsible_match)]
 pub fn load_all_verbs(xml_data: &str) -> Result<Vec<Verb>, Box<dyn std::error::Error>> {
     let mut xml_reader = Reader::from_str(xml_data);
     xml_reader.trim_text(true);
@@ -193,7 +232,147 @@ pub fn load_all_verbs(xml_data: &str) -> Result<Vec<Verb>, Box<dyn std::error::E
     Ok(verbs)
 }
 
+/// Header of verbs-fr.xml: license comment, DTD and the note on line ordering
+const VERBS_XML_HEADER: &str = r#"<?xml version="1.0"?>
+
+<!--
+	verbs-fr.xml - List of French verbs with their conjugation template
+	Copyright (C) 2003-2016 Pierre Sarrazin <http://sarrazip.com/>
+
+	This program is free software; you can redistribute it and/or
+	modify it under the terms of the GNU General Public License
+	as published by the Free Software Foundation; either version 2
+	of the License, or (at your option) any later version.
+
+	This program is distributed in the hope that it will be useful,
+	but WITHOUT ANY WARRANTY; without even the implied warranty of
+	MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+	GNU General Public License for more details.
+
+	You should have received a copy of the GNU General Public License
+	along with this program; if not, write to the Free Software
+	Foundation, Inc., 51 Franklin Street, Fifth Floor, Boston, MA
+	02110-1301, USA.
+-->
+
+<!DOCTYPE verbs-fr [
+	<!ELEMENT verbs-fr (v+)>
+	<!ELEMENT v (i, t, aspirate-h?)>
+	<!ELEMENT i (#PCDATA)>  <!-- infinitive -->
+	<!ELEMENT t (#PCDATA)>	<!-- template (radical:termination) -->
+	<!ELEMENT aspirate-h EMPTY>  <!-- useful for verbs starting with h -->
+]>
+
+<!--
+	The <v> lines are sorted with this filter:
+	env LANG=en_US.utf8 sort -f
+-->
+
+<verbs-fr>
+
+"#;
+
+/// Column at which the `<t>` element of a `<v>` line starts
+const TEMPLATE_COLUMN: usize = 32;
+
+/// Primary collation key approximating `LANG=en_US.utf8 sort -f`:
+/// punctuation is ignored, case and accents are folded
+fn collation_key(s: &str) -> String {
+    normalize_string(s)
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+/// Compare two `<v>` lines the way verbs-fr.xml is sorted
+fn collate(a: &str, b: &str) -> Ordering {
+    collation_key(a)
+        .cmp(&collation_key(b))
+        .then_with(|| a.cmp(b))
+}
+
+/// Format a single `<v>` line, padding with tabs so that `<t>` is aligned
+fn verb_line(verb: &Verb) -> String {
+    let head = format!("<v><i>{}</i>", partial_escape(&verb.verb));
+    let width = head.chars().count();
+    let tabs = if width < TEMPLATE_COLUMN {
+        (TEMPLATE_COLUMN - width).div_ceil(8)
+    } else {
+        1
+    };
+    let aspirate_h = if verb.aspirate_h {
+        " <aspirate-h/> "
+    } else {
+        ""
+    };
+    format!(
+        "{}{}<t>{}</t>{}</v>",
+        head,
+        "\t".repeat(tabs),
+        partial_escape(&verb.template),
+        aspirate_h
+    )
+}
+
+/// Serialize verbs to XML in the layout of verbs-fr.xml
+pub fn write_all_verbs(verbs: &[Verb]) -> String {
+    let mut lines: Vec<String> = verbs.iter().map(verb_line).collect();
+    lines.sort_by(|a, b| collate(a, b));
+
+    let mut xml = String::from(VERBS_XML_HEADER);
+    for line in lines {
+        xml.push_str(&line);
+        xml.push('\n');
+    }
+    xml.push_str("\n</verbs-fr>\n");
+    xml
+}
+
 /// Build a search index from loaded verbs for fast accent-insensitive search
 pub fn build_search_index(verbs: Vec<Verb>) -> VerbSearchIndex {
     VerbSearchIndex::new(verbs)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERBS_XML: &str = include_str!("../../data/verbs-fr.xml");
+
+    #[test]
+    fn verbs_round_trip() {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let xml = write_all_verbs(&verbs);
+        let reparsed = load_all_verbs(&xml).unwrap();
+        assert_eq!(verbs, reparsed);
+    }
+
+    #[test]
+    fn verb_line_layout() {
+        let verb = Verb {
+            verb: "hacher".to_string(),
+            template: "aim:er".to_string(),
+            aspirate_h: true,
+        };
+        assert_eq!(
+            verb_line(&verb),
+            "<v><i>hacher</i>\t\t<t>aim:er</t> <aspirate-h/> </v>"
+        );
+    }
+
+    #[test]
+    fn index_finds_both_spellings() {
+        let index = build_search_index(load_all_verbs(VERBS_XML).unwrap());
+        assert_eq!(index.find("paraître").unwrap().verb, "paraître");
+        assert_eq!(index.find("paraitre").unwrap().verb, "paraître");
+        assert!(index.find("croitre").is_none());
+        assert!(index.find("blurper").is_none());
+
+        let results: Vec<&str> = index
+            .search_by("paraitr", |_| true)
+            .iter()
+            .map(|verb| verb.verb.as_str())
+            .collect();
+        assert_eq!(results, ["paraître"]);
+    }
+}
@@ -0,0 +1,395 @@
+use crate::conjugation::{Mood, Person, Tense};
+use crate::drill;
+use crate::pdf::{self, Style};
+use crate::pronouns;
+use crate::render::escape_html;
+use crate::template::Template;
+use crate::verbs::Verb;
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Number of exercises when none is requested, and the most a worksheet can have
+pub const DEFAULT_COUNT: usize = 20;
+pub const MAX_COUNT: usize = 200;
+
+/// Blank left for the answer
+const BLANK: &str = "______________";
+
+/// Output document of a worksheet
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum WorksheetFormat {
+    #[default]
+    Html,
+    Pdf,
+}
+
+/// Choice of exercises, read from the query string or the command line
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WorksheetQuery {
+    /// Comma-separated infinitives; verbs of any template and group when omitted
+    pub verbs: Option<String>,
+    /// Only verbs of this template ("aim:er")
+    pub template: Option<String>,
+    /// Only verbs of this conjugation group (1, 2 or 3)
+    pub group: Option<u8>,
+    /// Comma-separated tense names; the present indicative when omitted
+    pub tenses: Option<String>,
+    /// Number of exercises
+    pub count: Option<usize>,
+    /// Seed making the worksheet reproducible; a random one is drawn and printed
+    /// on the worksheet when omitted
+    pub seed: Option<u64>,
+    pub title: Option<String>,
+    pub format: WorksheetFormat,
+}
+
+/// A sentence to complete and its answers
+#[derive(Debug, Clone, Serialize)]
+pub struct Exercise {
+    pub verb: String,
+    pub tense: Tense,
+    pub person: Person,
+    /// Subject and blank: "nous ______", "qu'il ______"
+    pub prompt: String,
+    /// Every accepted answer, with its subject: "nous finissons"
+    pub answers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Worksheet {
+    pub title: String,
+    pub seed: u64,
+    pub exercises: Vec<Exercise>,
+}
+
+/// Subject and blank of an exercise, elided like the answer ("j'______") and
+/// introduced by "que" in the subjunctive; `subject` is `None` in the imperative
+pub fn prompt(
+    subject: Option<&str>,
+    person: Person,
+    mood: Mood,
+    form: &str,
+    aspirate_h: bool,
+) -> String {
+    let Some(subject) = subject else {
+        let pronoun = match person {
+            Person::SecondSingular => "tu",
+            Person::FirstPlural => "nous",
+            _ => "vous",
+        };
+        return format!("{BLANK} ! ({pronoun})");
+    };
+    let clause = pronouns::elide(subject, form, aspirate_h);
+    let subject = clause.strip_suffix(form).unwrap_or(subject).trim_end();
+    let text = if subject.ends_with('\'') {
+        format!("{subject}{BLANK}")
+    } else {
+        format!("{subject} {BLANK}")
+    };
+    match mood {
+        Mood::Subjunctive => pronouns::elide("que", &text, false),
+        _ => text,
+    }
+}
+
+/// Pick random exercises matching the query, grouped by tense in the order the
+/// tenses were requested
+pub fn generate(
+    verbs: &[Verb],
+    templates: &HashMap<String, Template>,
+    query: &WorksheetQuery,
+) -> Result<Worksheet, String> {
+    let count = query.count.unwrap_or(DEFAULT_COUNT);
+    if count == 0 || count > MAX_COUNT {
+        return Err(format!("count must be between 1 and {MAX_COUNT}"));
+    }
+    let tenses = drill::parse_tenses(Some(
+        query
+            .tenses
+            .as_deref()
+            .unwrap_or(Tense::IndicativePresent.name()),
+    ))?;
+    if tenses.is_empty() {
+        return Err("no tense to practice".into());
+    }
+    let candidates: Vec<&Verb> = match &query.verbs {
+        Some(names) => drill::named(verbs, names)?,
+        None => drill::candidates(verbs, templates, query.template.as_deref(), query.group),
+    };
+    if candidates.is_empty() {
+        return Err("no verb matches the filters".into());
+    }
+
+    let seed = query.seed.unwrap_or_else(|| rand::rng().random());
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut seen = HashSet::new();
+    let mut exercises = Vec::new();
+    let mut conjugations = drill::Conjugations::new(templates);
+    // Small verb and tense sets have few distinct cells: give up on duplicates
+    // after enough attempts
+    for _ in 0..count * 20 {
+        if exercises.len() == count {
+            break;
+        }
+        let verb = *candidates.choose(&mut rng).unwrap();
+        let tense = *tenses.choose(&mut rng).unwrap();
+        let Some(drill) = drill::drill(verb, &mut conjugations, tense, &mut rng) else {
+            continue;
+        };
+        if !seen.insert((verb.verb.clone(), tense, drill.person)) {
+            continue;
+        }
+        let Some((forms, display)) = conjugations.cell(verb, tense, drill.person, drill.gender)
+        else {
+            continue;
+        };
+        exercises.push(Exercise {
+            verb: verb.verb.clone(),
+            tense,
+            person: drill.person,
+            prompt: prompt(
+                drill.subject,
+                drill.person,
+                drill.mood,
+                &forms[0],
+                verb.aspirate_h,
+            ),
+            answers: display,
+        });
+    }
+    if exercises.is_empty() {
+        return Err("no existing form matches the filters".into());
+    }
+    exercises.sort_by_key(|exercise| tenses.iter().position(|&t| t == exercise.tense));
+
+    Ok(Worksheet {
+        title: query
+            .title
+            .clone()
+            .unwrap_or_else(|| "Conjugation exercises".to_string()),
+        seed,
+        exercises,
+    })
+}
+
+/// Exercises of a worksheet split into runs of the same tense, with the number
+/// of the first exercise of each run
+fn sections(worksheet: &Worksheet) -> Vec<(Tense, usize, &[Exercise])> {
+    let mut sections = Vec::new();
+    let mut start = 0;
+    for chunk in worksheet.exercises.chunk_by(|a, b| a.tense == b.tense) {
+        sections.push((chunk[0].tense, start + 1, chunk));
+        start += chunk.len();
+    }
+    sections
+}
+
+/// Standalone HTML document, the answer key printed on its own page
+pub fn render_html(worksheet: &Worksheet) -> String {
+    let title = escape_html(&worksheet.title);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"fr\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>\n\
+         body {{ font-family: sans-serif; max-width: 40em; margin: 2em auto; }}\n\
+         li {{ margin: 0.8em 0; }}\n\
+         .verb {{ color: #555; }}\n\
+         .key {{ page-break-before: always; }}\n\
+         footer {{ color: #777; font-size: 0.8em; margin-top: 2em; }}\n\
+         </style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+    );
+    for (tense, start, exercises) in sections(worksheet) {
+        html.push_str(&format!(
+            "<h2>{}</h2>\n<ol start=\"{start}\">\n",
+            tense.label()
+        ));
+        for exercise in exercises {
+            html.push_str(&format!(
+                "<li>{} <span class=\"verb\">({})</span></li>\n",
+                escape_html(&exercise.prompt),
+                escape_html(&exercise.verb)
+            ));
+        }
+        html.push_str("</ol>\n");
+    }
+    html.push_str("<section class=\"key\">\n<h1>Answer key</h1>\n");
+    for (tense, start, exercises) in sections(worksheet) {
+        html.push_str(&format!(
+            "<h2>{}</h2>\n<ol start=\"{start}\">\n",
+            tense.label()
+        ));
+        for exercise in exercises {
+            html.push_str(&format!(
+                "<li>{}</li>\n",
+                escape_html(&exercise.answers.join(" / "))
+            ));
+        }
+        html.push_str("</ol>\n");
+    }
+    html.push_str(&format!(
+        "</section>\n<footer>Seed {}</footer>\n</body>\n</html>\n",
+        worksheet.seed
+    ));
+    html
+}
+
+/// PDF document, the answer key starting on a new page
+pub fn render_pdf(worksheet: &Worksheet) -> Vec<u8> {
+    let mut document = pdf::Document::new();
+    document.line(&worksheet.title, Style::Title, 0.0);
+    for (tense, start, exercises) in sections(worksheet) {
+        document.skip(6.0);
+        document.line(&tense.label(), Style::Heading, 0.0);
+        for (number, exercise) in (start..).zip(exercises) {
+            document.line(
+                &format!("{number}. {} ({})", exercise.prompt, exercise.verb),
+                Style::Body,
+                12.0,
+            );
+        }
+    }
+    document.skip(12.0);
+    document.line(&format!("Seed {}", worksheet.seed), Style::Small, 0.0);
+
+    document.page_break();
+    document.line("Answer key", Style::Title, 0.0);
+    for (tense, start, exercises) in sections(worksheet) {
+        document.skip(6.0);
+        document.line(&tense.label(), Style::Heading, 0.0);
+        for (number, exercise) in (start..).zip(exercises) {
+            document.line(
+                &format!("{number}. {}", exercise.answers.join(" / ")),
+                Style::Body,
+                12.0,
+            );
+        }
+    }
+    document.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template::load_all_templates;
+    use crate::verbs::load_all_verbs;
+
+    const VERBS_XML: &str = include_str!("../../data/verbs-fr.xml");
+    const CONJUGATION_XML: &str = include_str!("../../data/conjugation-fr.xml");
+
+    fn worksheet(query: &WorksheetQuery) -> Worksheet {
+        let verbs = load_all_verbs(VERBS_XML).unwrap();
+        let templates = load_all_templates(CONJUGATION_XML).unwrap();
+        generate(&verbs, &templates, query).unwrap()
+    }
+
+    #[test]
+    fn seed_reproduces_the_worksheet() {
+        let query = WorksheetQuery {
+            tenses: Some("indicative-present,subjunctive-present".to_string()),
+            count: Some(12),
+            seed: Some(42),
+            ..WorksheetQuery::default()
+        };
+        let first = worksheet(&query);
+        let second = worksheet(&query);
+        assert_eq!(first.seed, 42);
+        assert_eq!(first.exercises.len(), 12);
+        let exercises = |worksheet: &Worksheet| -> Vec<(String, String, Vec<String>)> {
+            worksheet
+                .exercises
+                .iter()
+                .map(|e| (e.verb.clone(), e.prompt.clone(), e.answers.clone()))
+                .collect()
+        };
+        assert_eq!(exercises(&first), exercises(&second));
+        assert_eq!(render_html(&first), render_html(&second));
+        // Exercises are grouped by tense in the requested order
+        assert!(
+            first
+                .exercises
+                .is_sorted_by_key(|exercise| exercise.tense != Tense::IndicativePresent)
+        );
+    }
+
+    #[test]
+    fn prompts_are_elided() {
+        use Mood::{Imperative, Indicative, Subjunctive};
+        // Prompt of a subject, "…" standing for the blank
+        let prompt_of = |subject: &str, mood, form: &str| {
+            let person = match subject {
+                "je" => Person::FirstSingular,
+                "tu" => Person::SecondSingular,
+                _ => Person::ThirdSingular,
+            };
+            prompt(Some(subject), person, mood, form, false).replace(BLANK, "…")
+        };
+        let cases = [
+            ("je", Indicative, "aime", "j'…"),
+            ("je", Indicative, "finis", "je …"),
+            ("je", Subjunctive, "aie", "que j'…"),
+            ("il", Subjunctive, "finisse", "qu'il …"),
+            ("elle", Subjunctive, "aime", "qu'elle …"),
+            ("tu", Subjunctive, "aimes", "que tu …"),
+        ];
+        for (subject, mood, form, expected) in cases {
+            assert_eq!(prompt_of(subject, mood, form), expected, "{subject} {form}");
+        }
+        // No elision before an aspirate h
+        let hais = prompt(Some("je"), Person::FirstSingular, Indicative, "hais", true);
+        assert_eq!(hais, format!("je {BLANK}"));
+        let imperative = prompt(None, Person::SecondPlural, Imperative, "aimez", false);
+        assert_eq!(imperative, format!("{BLANK} ! (vous)"));
+    }
+
+    #[test]
+    fn pdf_is_well_formed() {
+        let query = WorksheetQuery {
+            verbs: Some("aimer,finir,être".to_string()),
+            tenses: Some("indicative-present,conditional-past".to_string()),
+            count: Some(40),
+            seed: Some(1),
+            ..WorksheetQuery::default()
+        };
+        let pdf = render_pdf(&worksheet(&query));
+        // Text of the same length, the WinAnsi bytes of accented letters masked
+        let text: String = pdf
+            .iter()
+            .map(|&byte| if byte.is_ascii() { byte as char } else { '?' })
+            .collect();
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.ends_with("%%EOF\n"));
+
+        // The cross-reference table points at each object
+        let startxref: usize = text
+            .rsplit("startxref\n")
+            .next()
+            .and_then(|tail| tail.lines().next())
+            .and_then(|offset| offset.parse().ok())
+            .unwrap();
+        assert!(pdf[startxref..].starts_with(b"xref\n"));
+        let mut lines = text[startxref..].lines().skip(1);
+        let count: usize = lines.next().unwrap()["0 ".len()..].parse().unwrap();
+        let offsets: Vec<usize> = lines
+            .skip(1)
+            .take(count - 1)
+            .map(|line| line[..10].parse().unwrap())
+            .collect();
+        for (index, offset) in offsets.iter().enumerate() {
+            let header = format!("{} 0 obj\n", index + 1);
+            assert!(pdf[*offset..].starts_with(header.as_bytes()), "{header}");
+        }
+        assert!(text.contains(&format!("/Size {count} ")));
+
+        // Stream lengths match their content
+        for (start, _) in text.match_indices("<< /Length ") {
+            let rest = &text[start + "<< /Length ".len()..];
+            let length: usize = rest[..rest.find(' ').unwrap()].parse().unwrap();
+            let content = start + text[start..].find("stream\n").unwrap() + "stream\n".len();
+            assert!(pdf[content + length..].starts_with(b"\nendstream"));
+        }
+    }
+}